@@ -1,31 +1,81 @@
+use std::fmt;
+
+// A statement and the line it starts on, which thrown errors report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt {
+  pub kind: StmtKind,
+  pub line: usize
+}
+
 #[derive(Debug, Clone, PartialEq)]
-pub enum Stmt {
+pub enum StmtKind {
   Expr(Expr),
   VarDecl {
     mutable: bool,
-    name: String,
+    pattern: Pattern,
     value: Option<Expr>
   },
   FuncDecl {
-    params: Vec<String>,
+    params: Vec<Param>,
     name: String,
     body: Vec<Stmt>
   },
   Return {
     value: Option<Expr>
   },
+  // makes the function it is in a generator
+  Yield {
+    value: Option<Expr>
+  },
   If {
     condition: Expr,
     then_branch: Vec<Stmt>,
     else_branch: Option<Vec<Stmt>>
   },
   For {
-    ident: String,
+    pattern: Pattern,
     iterable: Box<Expr>,
     body: Vec<Stmt>
+  },
+  StructDecl {
+    name: String,
+    fields: Vec<String>
+  },
+  Impl {
+    name: String,
+    methods: Vec<Stmt>
+  },
+  EnumDecl {
+    name: String,
+    variants: Vec<Variant>
+  },
+  Throw {
+    value: Expr
+  },
+  // `try { } catch e { } finally { }`, with a catch, a finally or both
+  Try {
+    body: Vec<Stmt>,
+    catch: Option<Catch>,
+    finally: Option<Vec<Stmt>>
+  },
+  // `export` before a declaration at the top level of a file
+  Export {
+    decl: Box<Stmt>
+  },
+  // `import "./util.vig" as util;` or `import { add, PI } from "./math.vig";`
+  Import {
+    path: String,
+    bindings: ImportBindings
   }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportBindings {
+  // all exports of the module as one object
+  Module(String),
+  Names(Vec<String>)
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
   BinExp {
@@ -46,10 +96,19 @@ pub enum Expr {
     assignee: Box<Expr>,
     value: Box<Expr>
   },
-  Property(Property),
+  // `target += value`, `op` is the operator without the `=`
+  CompoundAssign {
+    assignee: Box<Expr>,
+    op: String,
+    value: Box<Expr>
+  },
   ObjectLit {
     properties: Vec<Property>
   },
+  StructLit {
+    name: String,
+    properties: Vec<Property>
+  },
   Member {
     object: Box<Expr>,
     property: Box<Expr>,
@@ -59,8 +118,45 @@ pub enum Expr {
     callee: Box<Expr>,
     args: Vec<Expr>
   },
+  MethodCall {
+    object: Box<Expr>,
+    method: String,
+    args: Vec<Expr>
+  },
   String {
     value: String
+  },
+  Match {
+    subject: Box<Expr>,
+    arms: Vec<MatchArm>
+  },
+  // `if` and `{ ... }` in the place of a value, they evaluate to their last expression
+  If {
+    condition: Box<Expr>,
+    then_branch: Vec<Stmt>,
+    else_branch: Option<Vec<Stmt>>
+  },
+  Block {
+    body: Vec<Stmt>
+  },
+  // `start..end` or `start..=end`, either end may be missing
+  Range {
+    start: Option<Box<Expr>>,
+    end: Option<Box<Expr>>,
+    inclusive: bool
+  },
+  // `...rest` in the target of a destructuring assignment
+  Spread {
+    expr: Box<Expr>
+  },
+  // `expr?`, unwraps `Some` and `Ok`, returns `None` and `Err` from the function
+  Propagate {
+    expr: Box<Expr>
+  },
+  // `name: value` in the arguments of a call
+  NamedArg {
+    name: String,
+    value: Box<Expr>
   }
 }
 
@@ -73,4 +169,168 @@ pub struct Program {
 pub struct Property {
   pub key: String,
   pub value: Option<Box<Expr>>
-}
\ No newline at end of file
+}
+// `Circle(r)` in `enum Shape { Circle(r), Empty }`, `Empty` has no fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+  pub name: String,
+  pub fields: Vec<String>
+}
+
+// A function parameter. `b = 10` has a default for when no argument is given,
+// `...rest` as the last parameter collects the remaining arguments in an array.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+  pub pattern: Pattern,
+  pub default: Option<Expr>
+}
+
+// `catch e { }` binds the thrown value to `e`, `catch { }` ignores it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Catch {
+  pub name: Option<String>,
+  pub body: Vec<Stmt>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+  pub pattern: Pattern,
+  pub guard: Option<Expr>,
+  pub body: Vec<Stmt>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+  // `_`
+  Wildcard,
+  // a name, which binds the matched value
+  Binding {
+    name: String
+  },
+  // numbers, strings, `true`, `false` and `null`, compared with `==`
+  Literal {
+    value: Expr
+  },
+  // `Shape::Circle(r)` or `Shape::Empty`, or `Some(x)` without an enum name for a
+  // variant constructor declared as a variable
+  Variant {
+    enum_name: Option<String>,
+    variant: String,
+    fields: Vec<Pattern>
+  },
+  // `[a, b]` matches arrays of exactly that length, `[a, ...rest]` of at least
+  // length 1 with the remaining elements collected in `rest`
+  Array {
+    elements: Vec<Pattern>
+  },
+  // `...name` or `...`, only inside an array pattern
+  Rest {
+    name: Option<String>
+  },
+  // `{ x, y: pattern }` matches objects and structs that have those keys
+  Object {
+    properties: Vec<(String, Pattern)>
+  }
+}
+
+// Whether a function body yields, which makes the function a generator. Only the
+// bodies of `if`, `for`, `try`, and of `if`, `match` and `{ }` expressions that stand
+// as a statement are searched, a nested function is a function of its own.
+pub fn contains_yield(body: &[Stmt]) -> bool {
+  body.iter().any(|stmt| match &stmt.kind {
+    StmtKind::Yield { .. } => true,
+    StmtKind::If { then_branch, else_branch, .. } | StmtKind::Expr(Expr::If { then_branch, else_branch, .. }) => {
+      contains_yield(then_branch) || else_branch.as_deref().is_some_and(contains_yield)
+    },
+    StmtKind::Expr(Expr::Block { body }) => contains_yield(body),
+    StmtKind::Expr(Expr::Match { arms, .. }) => arms.iter().any(|arm| contains_yield(&arm.body)),
+    StmtKind::For { body, .. } => contains_yield(body),
+    StmtKind::Try { body, catch, finally } => {
+      contains_yield(body) || catch.as_ref().is_some_and(|catch| contains_yield(&catch.body)) || finally.as_deref().is_some_and(contains_yield)
+    },
+    _ => false
+  })
+}
+
+// The names a declaration declares, which is what exporting it exports.
+pub fn declared_names(stmt: &StmtKind) -> Vec<String> {
+  let mut names = Vec::new();
+  match stmt {
+    StmtKind::VarDecl { pattern, .. } => pattern_names(pattern, &mut names),
+    StmtKind::FuncDecl { name, .. } | StmtKind::StructDecl { name, .. } | StmtKind::EnumDecl { name, .. } => names.push(name.clone()),
+    _ => ()
+  }
+  names
+}
+
+// The names a file exports, from the `export` declarations at its top level.
+pub fn exported_names(body: &[Stmt]) -> Vec<String> {
+  body.iter().flat_map(|stmt| match &stmt.kind {
+    StmtKind::Export { decl } => declared_names(&decl.kind),
+    _ => Vec::new()
+  }).collect()
+}
+
+fn pattern_names(pattern: &Pattern, names: &mut Vec<String>) {
+  match pattern {
+    Pattern::Binding { name } | Pattern::Rest { name: Some(name) } => names.push(name.clone()),
+    Pattern::Variant { fields: patterns, .. } | Pattern::Array { elements: patterns } => {
+      patterns.iter().for_each(|pattern| pattern_names(pattern, names));
+    },
+    Pattern::Object { properties } => properties.iter().for_each(|(_, pattern)| pattern_names(pattern, names)),
+    Pattern::Wildcard | Pattern::Literal { .. } | Pattern::Rest { name: None } => ()
+  }
+}
+
+// Patterns are shown as they are written, for function signatures and errors.
+impl fmt::Display for Pattern {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Pattern::Wildcard => write!(f, "_"),
+      Pattern::Binding { name } => write!(f, "{}", name),
+      Pattern::Literal { value } => write_literal(f, value),
+      Pattern::Variant { enum_name: Some(enum_name), variant, fields } if fields.is_empty() => write!(f, "{}::{}", enum_name, variant),
+      Pattern::Variant { enum_name: Some(enum_name), variant, fields } => write!(f, "{}::{}({})", enum_name, variant, join(fields)),
+      Pattern::Variant { enum_name: None, variant, fields } => write!(f, "{}({})", variant, join(fields)),
+      Pattern::Array { elements } => write!(f, "[{}]", join(elements)),
+      Pattern::Rest { name: Some(name) } => write!(f, "...{}", name),
+      Pattern::Rest { name: None } => write!(f, "..."),
+      Pattern::Object { properties } if properties.is_empty() => write!(f, "{{}}"),
+      Pattern::Object { properties } => {
+        let properties: Vec<String> = properties.iter().map(|(key, pattern)| match pattern {
+          Pattern::Binding { name } if name == key => key.clone(),
+          pattern => format!("{}: {}", key, pattern)
+        }).collect();
+        write!(f, "{{ {} }}", properties.join(", "))
+      }
+    }
+  }
+}
+
+// Only literal defaults are shown, others are left out as `...`.
+impl fmt::Display for Param {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.pattern)?;
+    match &self.default {
+      Some(value @ (Expr::IntLit { .. } | Expr::String { .. } | Expr::Ident { .. })) => {
+        write!(f, " = ")?;
+        write_literal(f, value)
+      },
+      Some(_) => write!(f, " = ..."),
+      None => Ok(())
+    }
+  }
+}
+
+fn write_literal(f: &mut fmt::Formatter<'_>, value: &Expr) -> fmt::Result {
+  match value {
+    Expr::IntLit { value } => write!(f, "{}", value),
+    Expr::String { value } => write!(f, "{:?}", value),
+    Expr::Ident { symbol } => write!(f, "{}", symbol),
+    value => write!(f, "{:?}", value)
+  }
+}
+
+pub fn join<T: fmt::Display>(items: &[T]) -> String {
+  items.iter().map(|item| item.to_string()).collect::<Vec<String>>().join(", ")
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum TokenType {
   IntLit,
@@ -16,6 +17,8 @@ pub enum TokenType {
   NotEqCompare,
   LessThan,
   GreaterThan,
+  LessThanEq,
+  GreaterThanEq,
   Not,
   Func,
   If,
@@ -28,17 +31,36 @@ pub enum TokenType {
   Colon,
   StringLit,
   BinOp,
+  CompoundAssign, // +=, -=, *=, /=, %=
+  Increment, // ++, --
   Let,
   Const,
   Mut,
   Ret,
+  Yield,
+  Struct,
+  Impl,
+  Enum,
+  Match,
+  Throw,
+  Try,
+  Catch,
+  Finally,
+  Import,
+  Export,
+  FatArrow, // =>
+  Question, // ?
+  Spread, // ...
+  Range, // ..
+  RangeInclusive, // ..=
   EOF
 }
 
 #[derive(Clone, Debug)]
 pub struct Token {
   pub token_type: TokenType,
-  pub value: String
+  pub value: String,
+  pub line: usize
 }
 
 fn create_reserved() -> HashMap<&'static str, TokenType> {
@@ -47,11 +69,22 @@ fn create_reserved() -> HashMap<&'static str, TokenType> {
   reserved.insert("const", TokenType::Const);
   reserved.insert("func", TokenType::Func);
   reserved.insert("ret", TokenType::Ret);
+  reserved.insert("yield", TokenType::Yield);
   reserved.insert("mut", TokenType::Mut);
   reserved.insert("if", TokenType::If);
   reserved.insert("else", TokenType::Else);
   reserved.insert("for", TokenType::For);
   reserved.insert("in", TokenType::In);
+  reserved.insert("struct", TokenType::Struct);
+  reserved.insert("impl", TokenType::Impl);
+  reserved.insert("enum", TokenType::Enum);
+  reserved.insert("match", TokenType::Match);
+  reserved.insert("throw", TokenType::Throw);
+  reserved.insert("try", TokenType::Try);
+  reserved.insert("catch", TokenType::Catch);
+  reserved.insert("finally", TokenType::Finally);
+  reserved.insert("import", TokenType::Import);
+  reserved.insert("export", TokenType::Export);
   reserved
 }
 
@@ -60,6 +93,7 @@ pub fn tokenize(src: &str) -> Vec<Token> {
   let mut tokens = Vec::new();
 
   let mut chars = src.chars().peekable();
+  let mut line = 1;
 
   while let Some(ch) = chars.next() {
 
@@ -74,65 +108,132 @@ pub fn tokenize(src: &str) -> Vec<Token> {
           panic!("Unterminated string literal");
         }
         chars.next();
-        tokens.push(Token { token_type: TokenType::StringLit, value: buffer });
+        let lines = buffer.matches('\n').count();
+        tokens.push(Token { token_type: TokenType::StringLit, value: buffer, line });
+        line += lines;
       },
-      '(' => tokens.push(Token { token_type: TokenType::OpenParen, value: ch.to_string() }),
-      ')' => tokens.push(Token { token_type: TokenType::CloseParen, value: ch.to_string() }),
-      '{' => tokens.push(Token { token_type: TokenType::OpenBrace, value: ch.to_string() }),
-      '}' => tokens.push(Token { token_type: TokenType::CloseBrace, value: ch.to_string() }),
-      '[' => tokens.push(Token { token_type: TokenType::OpenBracket, value: ch.to_string() }),
-      ']' => tokens.push(Token { token_type: TokenType::CloseBracket, value: ch.to_string() }),
+      '(' => tokens.push(Token { token_type: TokenType::OpenParen, value: ch.to_string(), line }),
+      ')' => tokens.push(Token { token_type: TokenType::CloseParen, value: ch.to_string(), line }),
+      '{' => tokens.push(Token { token_type: TokenType::OpenBrace, value: ch.to_string(), line }),
+      '}' => tokens.push(Token { token_type: TokenType::CloseBrace, value: ch.to_string(), line }),
+      '[' => tokens.push(Token { token_type: TokenType::OpenBracket, value: ch.to_string(), line }),
+      ']' => tokens.push(Token { token_type: TokenType::CloseBracket, value: ch.to_string(), line }),
       ':' => {
         if let Some(':') = chars.peek() {
           chars.next();
-          tokens.push(Token { token_type: TokenType::MemAccess, value: "::".to_string() });
+          tokens.push(Token { token_type: TokenType::MemAccess, value: "::".to_string(), line });
         } else {
-          tokens.push(Token { token_type: TokenType::Colon, value: ch.to_string() });
+          tokens.push(Token { token_type: TokenType::Colon, value: ch.to_string(), line });
         }
       }
-      ',' => tokens.push(Token { token_type: TokenType::Comma, value: ch.to_string() }),
-      '.' => tokens.push(Token { token_type: TokenType::Dot, value: ch.to_string() }),
+      ',' => tokens.push(Token { token_type: TokenType::Comma, value: ch.to_string(), line }),
+      '.' => {
+        let mut lookahead = chars.clone();
+        if lookahead.next() == Some('.') && lookahead.next() == Some('.') {
+          chars.next();
+          chars.next();
+          tokens.push(Token { token_type: TokenType::Spread, value: "...".to_string(), line });
+        } else if let Some('.') = chars.peek() {
+          chars.next();
+          if let Some('=') = chars.peek() {
+            chars.next();
+            tokens.push(Token { token_type: TokenType::RangeInclusive, value: "..=".to_string(), line });
+          } else {
+            tokens.push(Token { token_type: TokenType::Range, value: "..".to_string(), line });
+          }
+        } else {
+          tokens.push(Token { token_type: TokenType::Dot, value: ch.to_string(), line });
+        }
+      },
       '=' => {
         if let Some('=') = chars.peek() {
           chars.next();
-          tokens.push(Token { token_type: TokenType::EqCompare, value: "==".to_string() });
+          tokens.push(Token { token_type: TokenType::EqCompare, value: "==".to_string(), line });
+        } else if let Some('>') = chars.peek() {
+          chars.next();
+          tokens.push(Token { token_type: TokenType::FatArrow, value: "=>".to_string(), line });
         } else {
-          tokens.push(Token { token_type: TokenType::Eq, value: ch.to_string() });
+          tokens.push(Token { token_type: TokenType::Eq, value: ch.to_string(), line });
         }
       },
       '!' => {
         if let Some('=') = chars.peek() {
           chars.next();
-          tokens.push(Token { token_type: TokenType::NotEqCompare, value: "!=".to_string() });
+          tokens.push(Token { token_type: TokenType::NotEqCompare, value: "!=".to_string(), line });
         } else {
-          tokens.push(Token { token_type: TokenType::Not, value: ch.to_string() });
+          tokens.push(Token { token_type: TokenType::Not, value: ch.to_string(), line });
+        }
+      },
+      '>' => {
+        if let Some('=') = chars.peek() {
+          chars.next();
+          tokens.push(Token { token_type: TokenType::GreaterThanEq, value: ">=".to_string(), line });
+        } else {
+          tokens.push(Token { token_type: TokenType::GreaterThan, value: ch.to_string(), line });
+        }
+      },
+      '<' => {
+        if let Some('=') = chars.peek() {
+          chars.next();
+          tokens.push(Token { token_type: TokenType::LessThanEq, value: "<=".to_string(), line });
+        } else {
+          tokens.push(Token { token_type: TokenType::LessThan, value: ch.to_string(), line });
+        }
+      },
+      '?' => tokens.push(Token { token_type: TokenType::Question, value: ch.to_string(), line }),
+      ';' => tokens.push(Token { token_type: TokenType::Semi, value: ch.to_string(), line }),
+      // a comment runs to the end of the line
+      '/' if chars.peek() == Some(&'/') => {
+        while chars.peek().is_some_and(|ch| *ch != '\n') {
+          chars.next();
+        }
+      },
+      '+' | '-' if chars.peek() == Some(&ch) => {
+        chars.next();
+        tokens.push(Token { token_type: TokenType::Increment, value: format!("{}{}", ch, ch), line });
+      },
+      '+' | '-' | '*' | '/' | '%' => {
+        if let Some('=') = chars.peek() {
+          chars.next();
+          tokens.push(Token { token_type: TokenType::CompoundAssign, value: format!("{}=", ch), line });
+        } else {
+          tokens.push(Token { token_type: TokenType::BinOp, value: ch.to_string(), line });
         }
       },
-      '>' => tokens.push(Token { token_type: TokenType::GreaterThan, value: ch.to_string() }),
-      '<' => tokens.push(Token { token_type: TokenType::LessThan, value: ch.to_string() }),
-      ';' => tokens.push(Token { token_type: TokenType::Semi, value: ch.to_string() }),
-      '+' | '-' | '*' | '/' | '%' => tokens.push(Token { token_type: TokenType::BinOp, value: ch.to_string() }),
       '0' ..= '9' => {
         let mut value = ch.to_string();
         while let Some('0'..='9') = chars.peek() {
           value.push(chars.next().unwrap());
         }
-        tokens.push(Token { token_type: TokenType::IntLit, value });
+        // `1.5`, but not `1..5` or `1.round()`
+        let mut lookahead = chars.clone();
+        if lookahead.next() == Some('.') && lookahead.next().is_some_and(|ch| ch.is_ascii_digit()) {
+          value.push(chars.next().unwrap());
+          while let Some('0'..='9') = chars.peek() {
+            value.push(chars.next().unwrap());
+          }
+        }
+        tokens.push(Token { token_type: TokenType::IntLit, value, line });
       },
-      'a' ..= 'z' | 'A' ..= 'Z' => {
+      'a' ..= 'z' | 'A' ..= 'Z' | '_' => {
         let mut value = ch.to_string();
-        while let Some('a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '!') = chars.peek() {
+        while let Some(&next @ ('a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '!')) = chars.peek() {
+          // the `-` of `x-=1` and `x--` is an operator after the name, not part of it
+          if next == '-' && matches!(chars.clone().nth(1), Some('=' | '-')) {
+            break;
+          }
           value.push(chars.next().unwrap());
         }
         if let Some(token_type) = reserved.get(&value[..]) {
-          tokens.push(Token { token_type: (*token_type).clone(), value });
+          tokens.push(Token { token_type: (*token_type).clone(), value, line });
         } else {
-          tokens.push(Token { token_type: TokenType::Ident, value });
+          tokens.push(Token { token_type: TokenType::Ident, value, line });
         }
       },
+      '\n' => line += 1,
       _ => ()
     }
   }
-  tokens.push(Token { token_type: TokenType::EOF, value: String::new() });
+  tokens.push(Token { token_type: TokenType::EOF, value: String::new(), line });
   tokens
 }
\ No newline at end of file
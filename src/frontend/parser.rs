@@ -1,14 +1,17 @@
 use std::iter::Peekable;
-use crate::frontend::ast::{Expr, Stmt, Program, Property};
+use crate::frontend::ast::{Expr, Stmt, StmtKind, Program, Property, Variant, MatchArm, Pattern, Param, Catch, ImportBindings};
 use crate::frontend::lexer::{Token, TokenType};
 
 pub struct Parser<'a> {
   tokens: Peekable<std::slice::Iter<'a, Token>>,
+  // Set while parsing the head of an `if` or `for`, where a `{` after an identifier
+  // opens the body instead of a struct literal.
+  no_struct_literal: bool
 }
 
 impl<'a> Parser<'a> {
   pub fn new(tokens: &'a [Token]) -> Parser<'a> {
-    Parser { tokens: tokens.iter().peekable() }
+    Parser { tokens: tokens.iter().peekable(), no_struct_literal: false }
   }
 
   pub fn produce_ast(&mut self) -> Program {
@@ -42,18 +45,28 @@ impl<'a> Parser<'a> {
   }
 
   fn parse_stmt(&mut self) -> Stmt {
-    match self.at().token_type {
+    let line = self.at().line;
+    let kind = match self.at().token_type {
       TokenType::Let => self.parse_var_decl(),
       TokenType::Const => self.parse_var_decl(),
       TokenType::Func => self.parse_func_decl(),
       TokenType::Ret => self.parse_return(),
+      TokenType::Yield => self.parse_yield(),
       TokenType::If => self.parse_if_stmt(),
       TokenType::For => self.parse_for_stmt(),
+      TokenType::Struct => self.parse_struct_decl(),
+      TokenType::Impl => self.parse_impl(),
+      TokenType::Enum => self.parse_enum_decl(),
+      TokenType::Throw => self.parse_throw(),
+      TokenType::Try => self.parse_try(),
+      TokenType::Export => self.parse_export(),
+      TokenType::Import => self.parse_import(),
       _ => {
         let expr = self.parse_expr();
-        Stmt::Expr(expr)
+        StmtKind::Expr(expr)
       }
-    }
+    };
+    Stmt { kind, line }
   }
 
   fn parse_expr(&mut self) -> Expr {
@@ -61,29 +74,68 @@ impl<'a> Parser<'a> {
     // self.parse_additive_expr()
   }
 
+  // An expression in the head of an `if` or `for`, which can't be a struct literal.
+  fn parse_restricted_expr(&mut self) -> Expr {
+    let restricted = std::mem::replace(&mut self.no_struct_literal, true);
+    let expr = self.parse_expr();
+    self.no_struct_literal = restricted;
+    expr
+  }
+
+  // An expression enclosed in brackets of its own, where struct literals are fine again.
+  fn parse_nested_expr(&mut self) -> Expr {
+    let restricted = std::mem::replace(&mut self.no_struct_literal, false);
+    let expr = self.parse_expr();
+    self.no_struct_literal = restricted;
+    expr
+  }
+
   fn parse_assignment_expr(&mut self) -> Expr {
-    let left = self.parse_object_expr();
+    let left = self.parse_comparison_expr();
     if self.at().token_type == TokenType::Eq {
       self.consume();
       let value = self.parse_expr();
       Expr::Assign { assignee: Box::new(left), value: Box::new(value) }
     }
+    else if self.at().token_type == TokenType::CompoundAssign {
+      let op = self.consume().value.trim_end_matches('=').to_string();
+      let value = self.parse_expr();
+      Expr::CompoundAssign { assignee: Box::new(left), op, value: Box::new(value) }
+    }
+    // `x++` and `x--` are `x += 1` and `x -= 1`
+    else if self.at().token_type == TokenType::Increment {
+      let op = self.consume().value[..1].to_string();
+      Expr::CompoundAssign { assignee: Box::new(left), op, value: Box::new(Expr::IntLit { value: 1.0 }) }
+    }
     else {
       left
     }
   }
 
-  fn parse_object_expr(&mut self) -> Expr {
-    if self.at().token_type != TokenType::OpenBrace {
-      return self.parse_additive_expr();
+  // `{}`, `{ key: ...`, `{ key, ...` and `{ key }` start an object, any other `{` a block.
+  fn is_object_start(&self) -> bool {
+    let mut tokens = self.tokens.clone().skip(1);
+    match tokens.next().map(|token| &token.token_type) {
+      Some(TokenType::CloseBrace) => true,
+      Some(TokenType::Ident) => matches!(
+        tokens.next().map(|token| &token.token_type),
+        Some(TokenType::Colon | TokenType::Comma | TokenType::CloseBrace)
+      ),
+      _ => false
     }
+  }
 
-    self.consume(); // consume the open brace
+  fn parse_object_expr(&mut self) -> Expr {
+    Expr::ObjectLit { properties: self.parse_properties("Object") }
+  }
+
+  fn parse_properties(&mut self, kind: &str) -> Vec<Property> {
+    self.consume_expected(TokenType::OpenBrace, &format!("Expected a '{{' to open {} literal.", kind));
     let mut properties = Vec::new();
     while self.not_eof() && self.at().token_type != TokenType::CloseBrace {
       // { key: value, key2: value }
       // { key }
-      let key = self.consume_expected(TokenType::Ident, "Object literal key expected.");
+      let key = self.consume_expected(TokenType::Ident, &format!("{} literal key expected.", kind));
       if self.at().token_type == TokenType::Comma {
         self.consume();
         properties.push(Property { key: key.value, value: None });
@@ -94,23 +146,66 @@ impl<'a> Parser<'a> {
         continue;
       }
 
-      self.consume_expected(TokenType::Colon, "Missing colon following identifier in Object literal.");
-      let value = self.parse_expr();
+      self.consume_expected(TokenType::Colon, &format!("Missing colon following identifier in {} literal.", kind));
+      let value = self.parse_nested_expr();
       properties.push(Property { key: key.value, value: Some(Box::new(value)) });
 
       if self.at().token_type != TokenType::CloseBrace {
-        self.consume_expected(TokenType::Comma, "Object literal missing comma or closing brace following property.");
+        self.consume_expected(TokenType::Comma, &format!("{} literal missing comma or closing brace following property.", kind));
       }
     }
 
-    self.consume_expected(TokenType::CloseBrace, "Object literal missing closing brace.");
-    Expr::ObjectLit { properties }
+    self.consume_expected(TokenType::CloseBrace, &format!("{} literal missing closing brace.", kind));
+    properties
+  }
+
+  fn parse_comparison_expr(&mut self) -> Expr {
+    let mut left = self.parse_range_expr();
+
+    while matches!(
+      self.at().token_type,
+      TokenType::EqCompare | TokenType::NotEqCompare | TokenType::LessThan | TokenType::GreaterThan | TokenType::LessThanEq | TokenType::GreaterThanEq | TokenType::In
+    ) {
+      let op = self.consume().value;
+      let right = self.parse_range_expr();
+      left = Expr::BinExp {
+        left: Box::new(left),
+        op,
+        right: Box::new(right)
+      };
+    }
+    left
+  }
+
+  // `a..b`, `a..=b`, `a..` and `..b`, binding looser than arithmetic so `0..n - 1`
+  // ends at `n - 1`, and tighter than comparisons so `x in 0..10` works.
+  fn parse_range_expr(&mut self) -> Expr {
+    let start = match self.at().token_type {
+      TokenType::Range | TokenType::RangeInclusive => None,
+      _ => Some(Box::new(self.parse_additive_expr()))
+    };
+    let inclusive = match self.at().token_type {
+      TokenType::Range => false,
+      TokenType::RangeInclusive => true,
+      _ => return *start.unwrap()
+    };
+    self.consume();
+    let has_end = match self.at().token_type {
+      TokenType::CloseBracket | TokenType::CloseParen | TokenType::CloseBrace | TokenType::Comma | TokenType::Semi | TokenType::EOF => false,
+      TokenType::OpenBrace => !self.no_struct_literal,
+      _ => true
+    };
+    let end = if has_end { Some(Box::new(self.parse_additive_expr())) } else { None };
+    if inclusive && end.is_none() {
+      panic!("Parse Error\nAn inclusive range '..=' needs an end: {:?}", self.at());
+    }
+    Expr::Range { start, end, inclusive }
   }
 
   fn parse_additive_expr(&mut self) -> Expr {
     let mut left = self.parse_multiplicitive_expr();
 
-    while self.at().value == "+" || self.at().value == "-" || self.at().value == "==" || self.at().value == "!=" || self.at().value == "<" || self.at().value == ">" || self.at().value == "in" {
+    while self.at().token_type == TokenType::BinOp && (self.at().value == "+" || self.at().value == "-") {
       let op = self.consume().value;
       let right = self.parse_multiplicitive_expr();
       left = Expr::BinExp { 
@@ -140,20 +235,39 @@ impl<'a> Parser<'a> {
   fn parse_primary_expr(&mut self) -> Expr {
     let tk = self.at().token_type.clone();
     match tk {
-      TokenType::Ident => Expr::Ident { symbol: self.consume().value },
+      TokenType::Ident => {
+        let symbol = self.consume().value;
+        if self.at().token_type == TokenType::OpenBrace && !self.no_struct_literal {
+          Expr::StructLit { name: symbol, properties: self.parse_properties("Struct") }
+        } else {
+          Expr::Ident { symbol }
+        }
+      },
       TokenType::IntLit => Expr::IntLit { value: self.consume().value.parse::<f32>().unwrap() },
       TokenType::OpenParen => {
         self.consume(); // consume the open paren
-        let expr = self.parse_expr();
+        let expr = self.parse_nested_expr();
         self.consume_expected(TokenType::CloseParen, "Unexpected token found inside parenthesised expression. Expected closing parenthesis, but got"); // consume the close paren
         expr
       },
       TokenType::StringLit => Expr::String { value: self.consume().value },
+      TokenType::OpenBrace if self.is_object_start() => self.parse_object_expr(),
+      TokenType::OpenBrace => Expr::Block { body: self.parse_block() },
+      TokenType::If => {
+        let StmtKind::If { condition, then_branch, else_branch } = self.parse_if_stmt() else { unreachable!() };
+        Expr::If { condition: Box::new(condition), then_branch, else_branch }
+      },
+      TokenType::Match => self.parse_match_expr(),
       TokenType::OpenBracket => {
         self.consume();
         let mut elements = Vec::new();
         while self.at().token_type != TokenType::EOF && self.at().token_type != TokenType::CloseBracket {
-          elements.push(self.parse_expr());
+          if self.at().token_type == TokenType::Spread {
+            self.consume();
+            elements.push(Expr::Spread { expr: Box::new(self.parse_nested_expr()) });
+          } else {
+            elements.push(self.parse_nested_expr());
+          }
           if self.at().token_type != TokenType::CloseBracket {
             self.consume_expected(TokenType::Comma, "Expected a ','");
           }
@@ -166,9 +280,9 @@ impl<'a> Parser<'a> {
   }
 
 
-  fn parse_var_decl(&mut self) -> Stmt {
+  fn parse_var_decl(&mut self) -> StmtKind {
     let token_type = self.consume().token_type;
-    let var: Stmt = match token_type {
+    let var: StmtKind = match token_type {
       TokenType::Let => {
         let mutable: bool;
         if self.at().token_type == TokenType::Mut {
@@ -178,31 +292,34 @@ impl<'a> Parser<'a> {
           mutable = false;
         }
         
-        let ident = self.consume_expected(TokenType::Ident, "Expected a Identifier.");
+        let pattern = self.parse_pattern();
         if self.at().token_type == TokenType::Semi {
+          if !matches!(pattern, Pattern::Binding { .. }) {
+            panic!("Parse Error\nA destructuring declaration needs a value: let {}", pattern);
+          }
           self.consume();
-          Stmt::VarDecl {
+          StmtKind::VarDecl {
             mutable,
-            name: ident.value,
+            pattern,
             value: None
           }
         } else {
           self.consume_expected(TokenType::Eq, "Expected a '='");
           let expr = self.parse_expr();
           self.consume_expected(TokenType::Semi, "Expected a ';'");
-          Stmt::VarDecl {
+          StmtKind::VarDecl {
             mutable,
-            name: ident.value,
+            pattern,
             value: Some(expr)
           }
         }
       },
       TokenType::Const => {
-        let ident = self.consume_expected(TokenType::Ident, "Expected a Identifier.");
+        let pattern = self.parse_pattern();
         self.consume_expected(TokenType::Eq, "Expected a '='");
-        let decl = Stmt::VarDecl {
+        let decl = StmtKind::VarDecl {
           mutable: false,
-          name: ident.value,
+          pattern,
           value: Some(self.parse_expr())
         };
         self.consume_expected(TokenType::Semi, "Expected a ';'");
@@ -213,62 +330,206 @@ impl<'a> Parser<'a> {
     var
   }
 
-  fn parse_func_decl(&mut self) -> Stmt {
+  fn parse_func_decl(&mut self) -> StmtKind {
     self.consume();
     let name = self.consume_expected(TokenType::Ident, "Expected a Identifier as function name.").value;
-    let params: Vec<String> = self.parse_args().iter().map(|arg| {
-      match arg {
-        Expr::Ident { symbol } => symbol.clone(),
-        _ => panic!("Expected an identifier as function argument")
-      }
-    }).collect();
+    self.consume_expected(TokenType::OpenParen, "Expected a '(' to open the parameters.");
+    let params = self.parse_params();
     let body = self.parse_block();
-    let func = Stmt::FuncDecl {
+    StmtKind::FuncDecl {
       params,
       name,
       body
-    };
-    func
+    }
+  }
+
+  // a, b = default, ...rest)
+  fn parse_params(&mut self) -> Vec<Param> {
+    let mut params = Vec::new();
+    while self.not_eof() && self.at().token_type != TokenType::CloseParen {
+      if self.at().token_type == TokenType::Spread {
+        self.consume();
+        let name = match self.at().token_type {
+          TokenType::Ident => Some(self.consume().value),
+          _ => None
+        };
+        params.push(Param { pattern: Pattern::Rest { name }, default: None });
+        if self.at().token_type != TokenType::CloseParen {
+          panic!("Parse Error\nThe '...' parameter has to be the last one: {:?}", self.at());
+        }
+        break;
+      }
+      let pattern = self.parse_pattern();
+      let mut default = None;
+      if self.at().token_type == TokenType::Eq {
+        self.consume();
+        default = Some(self.parse_nested_expr());
+      }
+      params.push(Param { pattern, default });
+      if self.at().token_type != TokenType::CloseParen {
+        self.consume_expected(TokenType::Comma, "Expected a ',' between parameters.");
+      }
+    }
+    self.consume_expected(TokenType::CloseParen, "Expected a ')' to close the parameters.");
+    params
   }
 
-  fn parse_return(&mut self) -> Stmt {
+  fn parse_return(&mut self) -> StmtKind {
     self.consume();
     if self.at().token_type == TokenType::Semi {
       self.consume();
-      return Stmt::Return { value: None }
+      return StmtKind::Return { value: None }
     }
     let value = self.parse_expr();
     self.consume_expected(TokenType::Semi, "Expected a ';'");
-    Stmt::Return { value: Some(value) }
+    StmtKind::Return { value: Some(value) }
   }
 
-  fn parse_call_member_expr(&mut self) -> Expr {
-    let member = self.parse_member_expr();
+  fn parse_yield(&mut self) -> StmtKind {
+    self.consume();
+    if self.at().token_type == TokenType::Semi {
+      self.consume();
+      return StmtKind::Yield { value: None }
+    }
+    let value = self.parse_expr();
+    self.consume_expected(TokenType::Semi, "Expected a ';'");
+    StmtKind::Yield { value: Some(value) }
+  }
+
+  fn parse_throw(&mut self) -> StmtKind {
+    self.consume();
+    let value = self.parse_expr();
+    self.consume_expected(TokenType::Semi, "Expected a ';'");
+    StmtKind::Throw { value }
+  }
 
-    if self.at().token_type == TokenType::OpenParen {
-      self.parse_call_expr(member)
+  // try { } catch e { } finally { }
+  fn parse_try(&mut self) -> StmtKind {
+    self.consume();
+    let body = self.parse_block();
+    let mut catch = None;
+    if self.at().token_type == TokenType::Catch {
+      self.consume();
+      let mut name = None;
+      if self.at().token_type == TokenType::Ident {
+        name = Some(self.consume().value);
+      }
+      catch = Some(Catch { name, body: self.parse_block() });
+    }
+    let mut finally = None;
+    if self.at().token_type == TokenType::Finally {
+      self.consume();
+      finally = Some(self.parse_block());
+    }
+    if catch.is_none() && finally.is_none() {
+      panic!("Parse Error\nA try block needs a catch or a finally: {:?}", self.at());
+    }
+    StmtKind::Try { body, catch, finally }
+  }
+
+  // export func add(a, b) { }
+  fn parse_export(&mut self) -> StmtKind {
+    self.consume();
+    let decl = self.parse_stmt();
+    if !matches!(decl.kind, StmtKind::VarDecl { .. } | StmtKind::FuncDecl { .. } | StmtKind::StructDecl { .. } | StmtKind::EnumDecl { .. }) {
+      panic!("Parse Error\nOnly let, const, func, struct and enum declarations can be exported, line {}", decl.line);
+    }
+    StmtKind::Export { decl: Box::new(decl) }
+  }
+
+  // import "./util.vig" as util;
+  // import { add, PI } from "./math.vig";
+  fn parse_import(&mut self) -> StmtKind {
+    self.consume();
+    let (path, bindings) = if self.at().token_type == TokenType::OpenBrace {
+      self.consume();
+      let mut names = Vec::new();
+      while self.not_eof() && self.at().token_type != TokenType::CloseBrace {
+        names.push(self.consume_expected(TokenType::Ident, "Expected a name to import").value);
+        if self.at().token_type != TokenType::CloseBrace {
+          self.consume_expected(TokenType::Comma, "Expected a ','");
+        }
+      }
+      self.consume_expected(TokenType::CloseBrace, "Expected a '}' after the imported names");
+      self.consume_word("from");
+      let path = self.consume_expected(TokenType::StringLit, "Expected the path of the module").value;
+      (path, ImportBindings::Names(names))
     } else {
-      member
+      let path = self.consume_expected(TokenType::StringLit, "Expected the path of the module").value;
+      self.consume_word("as");
+      let name = self.consume_expected(TokenType::Ident, "Expected a name for the module").value;
+      (path, ImportBindings::Module(name))
+    };
+    self.consume_expected(TokenType::Semi, "Expected a ';'");
+    StmtKind::Import { path, bindings }
+  }
+
+  // `as` and `from` are only keywords inside an import, elsewhere they are names.
+  fn consume_word(&mut self, word: &str) {
+    let tk = self.consume();
+    if tk.token_type != TokenType::Ident || tk.value != word {
+      panic!("Parse Error\nExpected '{}': {:?}", word, tk);
     }
   }
 
+  fn parse_call_member_expr(&mut self) -> Expr {
+    let mut expr = self.parse_member_expr();
+
+    loop {
+      expr = match self.at().token_type {
+        TokenType::OpenParen => self.parse_call_expr(expr),
+        TokenType::Dot => self.parse_method_call_expr(expr),
+        TokenType::MemAccess | TokenType::OpenBracket => self.parse_member_access(expr),
+        TokenType::Question => {
+          self.consume();
+          Expr::Propagate { expr: Box::new(expr) }
+        },
+        _ => break
+      };
+    }
+    expr
+  }
+
   fn parse_call_expr(&mut self, callee: Expr) -> Expr {
-    let mut call_expr = Expr::Call {
+    Expr::Call {
       callee: Box::new(callee),
       args: self.parse_args()
-    };
-    
-    if self.at().token_type == TokenType::OpenParen {
-      call_expr = self.parse_call_expr(call_expr)
     }
-    call_expr
   }
 
+  fn parse_method_call_expr(&mut self, object: Expr) -> Expr {
+    self.consume_expected(TokenType::Dot, "Expected a '.'");
+    let method = self.consume_expected(TokenType::Ident, "Expected a method name after '.'").value;
+    if self.at().token_type != TokenType::OpenParen {
+      panic!("Parse Error\nExpected a '(' after method name {}: {:?}", method, self.at());
+    }
+    Expr::MethodCall {
+      object: Box::new(object),
+      method,
+      args: self.parse_args()
+    }
+  }
+
+  // Positional arguments, `...iterable` spreads and `name: value` named arguments,
+  // which come after the others.
   fn parse_args(&mut self) -> Vec<Expr> {
     let mut args = Vec::new();
     self.consume_expected(TokenType::OpenParen, "Expected a '('");
     while self.at().token_type != TokenType::CloseParen {
-      args.push(self.parse_expr());
+      let named = self.at().token_type == TokenType::Ident
+        && self.tokens.clone().nth(1).is_some_and(|next| next.token_type == TokenType::Colon);
+      if named {
+        let name = self.consume().value;
+        self.consume();
+        args.push(Expr::NamedArg { name, value: Box::new(self.parse_nested_expr()) });
+      } else if args.iter().any(|arg| matches!(arg, Expr::NamedArg { .. })) {
+        panic!("Parse Error\nPositional arguments have to come before named ones: {:?}", self.at());
+      } else if self.at().token_type == TokenType::Spread {
+        self.consume();
+        args.push(Expr::Spread { expr: Box::new(self.parse_nested_expr()) });
+      } else {
+        args.push(self.parse_nested_expr());
+      }
       if self.at().token_type != TokenType::CloseParen {
         self.consume_expected(TokenType::Comma, "Expected a ','");
       }
@@ -281,43 +542,53 @@ impl<'a> Parser<'a> {
     let mut object = self.parse_primary_expr();
 
     while self.at().token_type == TokenType::MemAccess || self.at().token_type == TokenType::OpenBracket {
-      let operator = self.consume();
-      let property: Expr;
-      let computed: bool;
+      object = self.parse_member_access(object);
+    }
+    object
+  }
 
-      if operator.token_type == TokenType::MemAccess {
-        computed = false;
-        property = self.parse_primary_expr();
+  fn parse_member_access(&mut self, object: Expr) -> Expr {
+    let operator = self.consume();
+    let property: Expr;
+    let computed: bool;
 
-        if !matches!(property, Expr::Ident { .. }) {
-          panic!("Expected an identifier after '.'");
-        }
-      } else {
-        computed = true;
-        property = self.parse_expr();
-        self.consume_expected(TokenType::CloseBracket, "Expected a ']'");
-      }
+    if operator.token_type == TokenType::MemAccess {
+      computed = false;
+      property = self.parse_primary_expr();
 
-      object = Expr::Member {
-        object: Box::new(object),
-        property: Box::new(property),
-        computed
+      if !matches!(property, Expr::Ident { .. }) {
+        panic!("Expected an identifier after '::'");
       }
+    } else {
+      computed = true;
+      property = self.parse_nested_expr();
+      self.consume_expected(TokenType::CloseBracket, "Expected a ']'");
+    }
+
+    Expr::Member {
+      object: Box::new(object),
+      property: Box::new(property),
+      computed
     }
-    object
   }
 
-  fn parse_if_stmt(&mut self) -> Stmt {
+  fn parse_if_stmt(&mut self) -> StmtKind {
     self.consume();
-    let condition = self.parse_expr();
+    let condition = self.parse_restricted_expr();
     let body = self.parse_block();
 
     let mut else_body: Option<Vec<Stmt>> = None;
     if self.at().token_type == TokenType::Else {
       self.consume();
-      else_body = Some(self.parse_block());
+      if self.at().token_type == TokenType::If {
+        // `else if` is an else block holding just the next if
+        let line = self.at().line;
+        else_body = Some(vec![Stmt { kind: self.parse_if_stmt(), line }]);
+      } else {
+        else_body = Some(self.parse_block());
+      }
     }
-    Stmt::If {
+    StmtKind::If {
       condition,
       then_branch: body,
       else_branch: else_body
@@ -329,27 +600,195 @@ impl<'a> Parser<'a> {
     let mut body: Vec<Stmt> = Vec::new();
 
     while self.not_eof() && self.at().token_type != TokenType::CloseBrace {
-      body.push(self.parse_stmt());
+      let stmt = self.parse_stmt();
+      if matches!(stmt.kind, StmtKind::Export { .. } | StmtKind::Import { .. }) {
+        panic!("Parse Error\nImports and exports can only be at the top level of a file, line {}", stmt.line);
+      }
+      body.push(stmt);
     }
     self.consume_expected(TokenType::CloseBrace, "Expected a '}' to close block.");
     body
   }
 
-  fn parse_for_stmt(&mut self) -> Stmt {
+  fn parse_for_stmt(&mut self) -> StmtKind {
     self.consume();
     self.consume_expected(TokenType::Let, "Expected a 'let' to declare loop variable.");
-    let ident = self.consume_expected(TokenType::Ident, "Expected a Identifier as loop variable.");
+    let pattern = self.parse_pattern();
     self.consume_expected(TokenType::In, "Expected a 'in' to start loop.");
 
-    let iterable = self.parse_expr();
+    let iterable = self.parse_restricted_expr();
     let body = self.parse_block();
 
-    Stmt::For {
-      ident: ident.value,
+    StmtKind::For {
+      pattern,
       iterable: Box::new(iterable),
       body
     }
   }
-}
 
+  fn parse_struct_decl(&mut self) -> StmtKind {
+    self.consume();
+    let name = self.consume_expected(TokenType::Ident, "Expected a Identifier as struct name.").value;
+    self.consume_expected(TokenType::OpenBrace, "Expected a '{' to open struct fields.");
+    let mut fields = Vec::new();
+    while self.not_eof() && self.at().token_type != TokenType::CloseBrace {
+      fields.push(self.consume_expected(TokenType::Ident, "Expected a Identifier as struct field.").value);
+      if self.at().token_type != TokenType::CloseBrace {
+        self.consume_expected(TokenType::Comma, "Expected a ',' between struct fields.");
+      }
+    }
+    self.consume_expected(TokenType::CloseBrace, "Expected a '}' to close struct fields.");
+    StmtKind::StructDecl { name, fields }
+  }
+
+  fn parse_impl(&mut self) -> StmtKind {
+    self.consume();
+    let name = self.consume_expected(TokenType::Ident, "Expected a struct name after 'impl'.").value;
+    self.consume_expected(TokenType::OpenBrace, "Expected a '{' to open impl block.");
+    let mut methods = Vec::new();
+    while self.not_eof() && self.at().token_type != TokenType::CloseBrace {
+      if self.at().token_type != TokenType::Func {
+        panic!("Parse Error\nOnly functions can be declared in an impl block: {:?}", self.at());
+      }
+      let line = self.at().line;
+      methods.push(Stmt { kind: self.parse_func_decl(), line });
+    }
+    self.consume_expected(TokenType::CloseBrace, "Expected a '}' to close impl block.");
+    StmtKind::Impl { name, methods }
+  }
+
+  fn parse_enum_decl(&mut self) -> StmtKind {
+    self.consume();
+    let name = self.consume_expected(TokenType::Ident, "Expected a Identifier as enum name.").value;
+    self.consume_expected(TokenType::OpenBrace, "Expected a '{' to open enum variants.");
+    let mut variants = Vec::new();
+    while self.not_eof() && self.at().token_type != TokenType::CloseBrace {
+      let variant = self.consume_expected(TokenType::Ident, "Expected a Identifier as enum variant.").value;
+      let mut fields = Vec::new();
+      if self.at().token_type == TokenType::OpenParen {
+        fields = self.parse_args().iter().map(|arg| {
+          match arg {
+            Expr::Ident { symbol } => symbol.clone(),
+            _ => panic!("Expected an identifier as field of variant {}", variant)
+          }
+        }).collect();
+      }
+      variants.push(Variant { name: variant, fields });
+      if self.at().token_type != TokenType::CloseBrace {
+        self.consume_expected(TokenType::Comma, "Expected a ',' between enum variants.");
+      }
+    }
+    self.consume_expected(TokenType::CloseBrace, "Expected a '}' to close enum variants.");
+    StmtKind::EnumDecl { name, variants }
+  }
+
+  // match value { pattern if guard => expr, pattern => { block } }
+  fn parse_match_expr(&mut self) -> Expr {
+    self.consume();
+    let subject = self.parse_restricted_expr();
+    self.consume_expected(TokenType::OpenBrace, "Expected a '{' to open match arms.");
+    let mut arms = Vec::new();
+    while self.not_eof() && self.at().token_type != TokenType::CloseBrace {
+      let pattern = self.parse_pattern();
+      let mut guard = None;
+      if self.at().token_type == TokenType::If {
+        self.consume();
+        guard = Some(self.parse_nested_expr());
+      }
+      self.consume_expected(TokenType::FatArrow, "Expected a '=>' after match pattern.");
+      if self.at().token_type == TokenType::OpenBrace {
+        arms.push(MatchArm { pattern, guard, body: self.parse_block() });
+        if self.at().token_type == TokenType::Comma {
+          self.consume();
+        }
+      } else {
+        let line = self.at().line;
+        let expr = self.parse_nested_expr();
+        arms.push(MatchArm { pattern, guard, body: vec![Stmt { kind: StmtKind::Expr(expr), line }] });
+        if self.at().token_type != TokenType::CloseBrace {
+          self.consume_expected(TokenType::Comma, "Expected a ',' after match arm.");
+        }
+      }
+    }
+    self.consume_expected(TokenType::CloseBrace, "Expected a '}' to close match arms.");
+    Expr::Match { subject: Box::new(subject), arms }
+  }
+
+  fn parse_pattern(&mut self) -> Pattern {
+    let tk = self.consume();
+    match tk.token_type {
+      TokenType::IntLit => Pattern::Literal { value: Expr::IntLit { value: tk.value.parse::<f32>().unwrap() } },
+      TokenType::StringLit => Pattern::Literal { value: Expr::String { value: tk.value } },
+      TokenType::BinOp if tk.value == "-" => {
+        let number = self.consume_expected(TokenType::IntLit, "Expected a number after '-' in pattern.");
+        Pattern::Literal { value: Expr::IntLit { value: -number.value.parse::<f32>().unwrap() } }
+      },
+      TokenType::Ident => match tk.value.as_str() {
+        "_" => Pattern::Wildcard,
+        "true" | "false" | "null" | "None" => Pattern::Literal { value: Expr::Ident { symbol: tk.value } },
+        _ if self.at().token_type == TokenType::MemAccess => {
+          self.consume();
+          let variant = self.consume_expected(TokenType::Ident, "Expected a variant name after '::' in pattern.").value;
+          let mut fields = Vec::new();
+          if self.at().token_type == TokenType::OpenParen {
+            self.consume();
+            fields = self.parse_pattern_list(TokenType::CloseParen, ")", false);
+          }
+          Pattern::Variant { enum_name: Some(tk.value), variant, fields }
+        },
+        _ if self.at().token_type == TokenType::OpenParen => {
+          self.consume();
+          let fields = self.parse_pattern_list(TokenType::CloseParen, ")", false);
+          Pattern::Variant { enum_name: None, variant: tk.value, fields }
+        },
+        _ => Pattern::Binding { name: tk.value }
+      },
+      TokenType::OpenBracket => Pattern::Array { elements: self.parse_pattern_list(TokenType::CloseBracket, "]", true) },
+      TokenType::OpenBrace => {
+        let mut properties = Vec::new();
+        while self.not_eof() && self.at().token_type != TokenType::CloseBrace {
+          let key = self.consume_expected(TokenType::Ident, "Object pattern key expected.").value;
+          let pattern = if self.at().token_type == TokenType::Colon {
+            self.consume();
+            self.parse_pattern()
+          } else {
+            Pattern::Binding { name: key.clone() }
+          };
+          properties.push((key, pattern));
+          if self.at().token_type != TokenType::CloseBrace {
+            self.consume_expected(TokenType::Comma, "Expected a ',' between object pattern properties.");
+          }
+        }
+        self.consume_expected(TokenType::CloseBrace, "Expected a '}' to close object pattern.");
+        Pattern::Object { properties }
+      },
+      _ => panic!("Parse Error\nUnexpected token in pattern: {:?}", tk)
+    }
+  }
 
+  // The patterns up to and including `close`, the opening bracket is already consumed.
+  // Array patterns may have one `...rest` among them.
+  fn parse_pattern_list(&mut self, close: TokenType, bracket: &str, allow_rest: bool) -> Vec<Pattern> {
+    let mut patterns = Vec::new();
+    while self.not_eof() && self.at().token_type != close {
+      if self.at().token_type == TokenType::Spread {
+        let spread = self.consume();
+        if !allow_rest || patterns.iter().any(|pattern| matches!(pattern, Pattern::Rest { .. })) {
+          panic!("Parse Error\nOnly one '...' is allowed, and only in an array pattern: {:?}", spread);
+        }
+        let name = match self.at().token_type {
+          TokenType::Ident => Some(self.consume().value),
+          _ => None
+        };
+        patterns.push(Pattern::Rest { name });
+      } else {
+        patterns.push(self.parse_pattern());
+      }
+      if self.at().token_type != close {
+        self.consume_expected(TokenType::Comma, "Expected a ',' between patterns.");
+      }
+    }
+    self.consume_expected(close, &format!("Expected a '{}' to close the patterns.", bracket));
+    patterns
+  }
+}
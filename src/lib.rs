@@ -0,0 +1,4 @@
+// The interpreter as a library, for hosts that embed vigscript. The binary in
+// main.rs is one of them.
+pub mod frontend;
+pub mod runtime;
@@ -1,20 +1,38 @@
 use std::fs;
-mod frontend;
-mod runtime;
+use std::path::PathBuf;
+use std::rc::Rc;
+use vigscript::{frontend, runtime};
 
 fn main() {
     // check if there is a file to run in the arguments
-    let args: Vec<String> = std::env::args().collect();
-    let mut env = runtime::environment::create_global_environment();
+    let mut args = std::env::args().skip(1);
+    let mut file = None;
+    let mut search_paths = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // another directory to look for imported modules in
+            "--path" => match args.next() {
+                Some(dir) => search_paths.push(PathBuf::from(dir)),
+                None => panic!("--path expects a directory"),
+            },
+            // only the natives, without the part of the standard library written in vigscript
+            "--no-prelude" => runtime::prelude::disable(),
+            _ => file = Some(arg),
+        }
+    }
+    runtime::modules::set_loader(Rc::new(runtime::modules::FileLoader::new(search_paths)));
+    let mut env = runtime::prelude::global_environment();
 
-    if args.len() > 1 {
-        let filename = &args[1];
+    if let Some(filename) = file {
         if !filename.ends_with(".vig") {
             panic!("File must end with .vig");
         }
-        let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
+        let contents = fs::read_to_string(&filename).expect("Something went wrong reading the file");
+        runtime::modules::set_main(&filename);
 
-        interpret(&contents, &mut env);
+        if !interpret(&contents, &mut env) {
+            std::process::exit(1);
+        }
     } else {
         repl(&mut env);
     }
@@ -34,12 +52,17 @@ fn repl(env: &mut runtime::environment::Environment) {
     }
 }
 
-fn interpret(input: &str, env: &mut runtime::environment::Environment) {
-    let tokens = frontend::lexer::tokenize(&input);
+// Returns false when the program ended with an error no `try` caught.
+fn interpret(input: &str, env: &mut runtime::environment::Environment) -> bool {
+    let tokens = frontend::lexer::tokenize(input);
     let mut parser = frontend::parser::Parser::new(&tokens);
     let ast = parser.produce_ast();
 
-    runtime::interpreter::evaluate(ast, env);
-    //let result = runtime::interpreter::evaluate(ast, env);
-    //println!("{:?}", result);
+    match runtime::interpreter::evaluate(ast, env) {
+        Err(runtime::errors::Interrupt::Throw(value)) => {
+            eprintln!("{}", runtime::errors::describe_uncaught(&value));
+            false
+        },
+        _ => true
+    }
 }
\ No newline at end of file
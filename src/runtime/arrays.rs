@@ -0,0 +1,151 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::runtime::errors::{throw, ErrorKind, Eval};
+use crate::runtime::values::{Arity, NativeBody, RuntimeValue};
+
+// The natives of the `Array` helper, which arrays also have as methods, `arr.map(f)`.
+// The others are in the prelude. The functions given to them are called with each
+// element, of the array as it was when the helper was called, so a function that
+// changes the array doesn't change which elements it gets.
+pub fn array_object() -> RuntimeValue {
+  let functions: [(&str, Arity, NativeBody); 9] = [
+    ("new", Arity::Between(0, 1), |args, _| {
+      let amount = match args.first() {
+        Some(RuntimeValue::Number { value }) => *value as usize,
+        _ => 0
+      };
+      Ok(RuntimeValue::array(vec![RuntimeValue::Null; amount]))
+    }),
+    ("map", Arity::Exact(2), |args, interpreter| {
+      let (elements, func) = array_and_function("Array::map", &args)?;
+      let mut res = Vec::with_capacity(elements.len());
+      for element in elements {
+        res.push(interpreter.call(func, vec![element])?);
+      }
+      Ok(RuntimeValue::array(res))
+    }),
+    ("filter", Arity::Exact(2), |args, interpreter| {
+      let (elements, func) = array_and_function("Array::filter", &args)?;
+      let mut res = Vec::new();
+      for element in elements {
+        if interpreter.call(func, vec![element.clone()])?.is_truthy() {
+          res.push(element);
+        }
+      }
+      Ok(RuntimeValue::array(res))
+    }),
+    // `reduce(arr, f, initial)` calls `f(total, element)` for every element, the
+    // first element is the initial total when none is given
+    ("reduce", Arity::Between(2, 3), |args, interpreter| {
+      let (elements, func) = array_and_function("Array::reduce", &args)?;
+      let mut elements = elements.into_iter();
+      let mut total = match args.get(2) {
+        Some(initial) => initial.clone(),
+        None => match elements.next() {
+          Some(first) => first,
+          None => throw!(ErrorKind::Value, "Array::reduce of an empty array needs an initial value")
+        }
+      };
+      for element in elements {
+        total = interpreter.call(func, vec![total, element])?;
+      }
+      Ok(total)
+    }),
+    // the first element the function holds for, or null
+    ("find", Arity::Exact(2), |args, interpreter| {
+      let (elements, func) = array_and_function("Array::find", &args)?;
+      for element in elements {
+        if interpreter.call(func, vec![element.clone()])?.is_truthy() {
+          return Ok(element);
+        }
+      }
+      Ok(RuntimeValue::Null)
+    }),
+    ("any", Arity::Exact(2), |args, interpreter| {
+      let (elements, func) = array_and_function("Array::any", &args)?;
+      for element in elements {
+        if interpreter.call(func, vec![element])?.is_truthy() {
+          return Ok(RuntimeValue::Bool { value: true });
+        }
+      }
+      Ok(RuntimeValue::Bool { value: false })
+    }),
+    ("all", Arity::Exact(2), |args, interpreter| {
+      let (elements, func) = array_and_function("Array::all", &args)?;
+      for element in elements {
+        if !interpreter.call(func, vec![element])?.is_truthy() {
+          return Ok(RuntimeValue::Bool { value: false });
+        }
+      }
+      Ok(RuntimeValue::Bool { value: true })
+    }),
+    ("for_each", Arity::Exact(2), |args, interpreter| {
+      let (elements, func) = array_and_function("Array::for_each", &args)?;
+      for element in elements {
+        interpreter.call(func, vec![element])?;
+      }
+      Ok(RuntimeValue::Null)
+    }),
+    // `sort_by(arr, f)` sorts in place like `sort`, with `f(a, b)` returning a number
+    // below 0 when `a` comes first, above 0 when `b` does and 0 when they are equal.
+    // Equal elements keep their order.
+    ("sort_by", Arity::Exact(2), |args, interpreter| {
+      let (elements, func) = array_and_function("Array::sort_by", &args)?;
+      let sorted = merge_sort(elements, &mut |lhs, rhs| {
+        match interpreter.call(func, vec![lhs.clone(), rhs.clone()])? {
+          RuntimeValue::Number { value } if value < 0.0 => Ok(Ordering::Less),
+          RuntimeValue::Number { value } if value > 0.0 => Ok(Ordering::Greater),
+          RuntimeValue::Number { .. } => Ok(Ordering::Equal),
+          other => throw!(ErrorKind::Type, "The function given to Array::sort_by has to return a number, not {:?}", other)
+        }
+      })?;
+      let RuntimeValue::Array { elements } = &args[0] else { unreachable!() };
+      *elements.borrow_mut() = sorted;
+      Ok(RuntimeValue::Null)
+    })
+  ];
+  let mut map = HashMap::new();
+  for (name, arity, body) in functions {
+    map.insert(name.to_string(), RuntimeValue::native(&format!("Array::{}", name), arity, body));
+  }
+  RuntimeValue::object(map)
+}
+
+// A copy of the elements, so calling the function can't conflict with borrowing them.
+fn array_and_function<'a>(name: &str, args: &'a [RuntimeValue]) -> Eval<(Vec<RuntimeValue>, &'a RuntimeValue)> {
+  let elements = match args.first() {
+    Some(RuntimeValue::Array { elements }) => elements.borrow().clone(),
+    _ => throw!(ErrorKind::Argument, "{} expects an array as first argument", name)
+  };
+  match args.get(1) {
+    Some(func @ (RuntimeValue::Function { .. } | RuntimeValue::NativeFunction(_) | RuntimeValue::Variant { .. })) => Ok((elements, func)),
+    _ => throw!(ErrorKind::Argument, "{} expects a function as second argument", name)
+  }
+}
+
+// A merge sort rather than `sort_by`, which can't stop at a comparison that throws
+// and may panic on one that isn't consistent.
+pub(crate) fn merge_sort(
+  mut values: Vec<RuntimeValue>,
+  compare: &mut dyn FnMut(&RuntimeValue, &RuntimeValue) -> Eval<Ordering>
+) -> Eval<Vec<RuntimeValue>> {
+  if values.len() <= 1 {
+    return Ok(values);
+  }
+  let right = values.split_off(values.len() / 2);
+  let mut left = merge_sort(values, compare)?.into_iter().peekable();
+  let mut right = merge_sort(right, compare)?.into_iter().peekable();
+  let mut res = Vec::with_capacity(left.len() + right.len());
+  while let (Some(lhs), Some(rhs)) = (left.peek(), right.peek()) {
+    // the left one goes first unless the right one is smaller, which keeps it stable
+    if compare(rhs, lhs)? == Ordering::Less {
+      res.push(right.next().unwrap());
+    } else {
+      res.push(left.next().unwrap());
+    }
+  }
+  res.extend(left);
+  res.extend(right);
+  Ok(res)
+}
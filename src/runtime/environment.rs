@@ -1,229 +1,343 @@
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
-use crate::{runtime::values::{self, RuntimeValue}, frontend::ast::Expr};
+use std::fmt;
+use std::rc::{Rc, Weak};
+use crate::runtime::errors::{self, throw, ErrorKind, Eval};
+use crate::runtime::arrays;
+use crate::runtime::format;
+use crate::runtime::iteration;
+use crate::runtime::math;
+use crate::runtime::objects;
+use crate::runtime::results;
+use crate::runtime::strings;
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::values::{self, heap::Trace, Arity};
+
+thread_local! {
+  // the globals hosts registered, in the order they did
+  static REGISTERED: RefCell<Vec<(String, values::RuntimeValue)>> = const { RefCell::new(Vec::new()) };
+}
+
+// Declares `value` as a constant in every global environment created after this, so
+// also in modules. Hosts that embed the interpreter expose their own natives and
+// objects of natives this way, a registered name takes the place of a built-in one.
+pub fn register_global(name: &str, value: values::RuntimeValue) {
+  REGISTERED.with(|registered| registered.borrow_mut().push((name.to_string(), value)));
+}
+
+// `register_native("db_query!", Arity::Exact(1), move |args, _| ...)`, the closure can
+// hold whatever state it needs.
+pub fn register_native(name: &str, arity: Arity, body: impl Fn(Vec<values::RuntimeValue>, &mut Interpreter) -> Eval + 'static) {
+  register_global(name, values::RuntimeValue::native(name, arity, body));
+}
 
 pub fn create_global_environment() -> Environment {
   let mut env = Environment::new(None);
-  env.declare_var("true".to_string(), values::RuntimeValue::Bool { value: true  }, false);
-  env.declare_var("false".to_string(), values::RuntimeValue::Bool { value: false }, false);
-  env.declare_var("null".to_string(), values::RuntimeValue::Null, false);
-
-  env.declare_var("print!".to_string(), values::RuntimeValue::NativeFunction { 
-    body: |args, _| {
-      for (j, arg) in args.iter().enumerate() {
-        match arg {
-          values::RuntimeValue::String { value } => print!("{}", value),
-          values::RuntimeValue::Number { value } => print!("{}", value),
-          values::RuntimeValue::Bool { value } => print!("{}", value),
-          values::RuntimeValue::Null => print!("null"),
-          values::RuntimeValue::Array { elements } => {
-            print!("[");
-            for (i, element) in elements.iter().enumerate() {
-              print!("{:?}", element);
-              if i != elements.len() - 1 {
-                print!(", ");
-              }
-            }
-            print!("]");
-          },
-          _ => print!("{:?}", arg)
-        }
-        if j != args.len() - 1 {
-          print!(" ");
-        }
-      }
-      print!("\n");
-      values::RuntimeValue::Null
+  env.define("true", values::RuntimeValue::Bool { value: true  });
+  env.define("false", values::RuntimeValue::Bool { value: false });
+  env.define("null", values::RuntimeValue::Null);
+
+  env.define("done", values::RuntimeValue::Done);
+
+  env.define("Error", values::RuntimeValue::StructType(errors::error_type()));
+
+  env.define("Option", values::RuntimeValue::EnumType(results::option_type()));
+  env.define("Some", values::RuntimeValue::Variant { enum_type: results::option_type(), index: 0 });
+  env.define("None", results::none());
+  env.define("Result", values::RuntimeValue::EnumType(results::result_type()));
+  env.define("Ok", values::RuntimeValue::Variant { enum_type: results::result_type(), index: 0 });
+  env.define("Err", values::RuntimeValue::Variant { enum_type: results::result_type(), index: 1 });
+
+  env.define("print!", values::RuntimeValue::native("print!", Arity::AtLeast(0), |args, _| {
+    let line: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    println!("{}", line.join(" "));
+    Ok(values::RuntimeValue::Null)
+  }));
+
+  env.define("format!", values::RuntimeValue::native("format!", Arity::AtLeast(1), |args, _| Ok(values::RuntimeValue::String { value: format_native_args("format!", &args)? })));
+
+  env.define("println!", values::RuntimeValue::native("println!", Arity::AtLeast(1), |args, _| {
+    println!("{}", format_native_args("println!", &args)?);
+    Ok(values::RuntimeValue::Null)
+  }));
+
+  env.define("eprintln!", values::RuntimeValue::native("eprintln!", Arity::AtLeast(1), |args, _| {
+    eprintln!("{}", format_native_args("eprintln!", &args)?);
+    Ok(values::RuntimeValue::Null)
+  }));
+
+  env.define("to_string!", values::RuntimeValue::native("to_string!", Arity::Between(1, 2), |args, _| {
+    let value = match (args.first(), args.get(1)) {
+      (Some(value), Some(values::RuntimeValue::Bool { value: true })) => format!("{:#}", value),
+      (Some(value), _) => value.to_string(),
+      (None, _) => throw!(ErrorKind::Argument, "to_string! expects a value")
+    };
+    Ok(values::RuntimeValue::String { value })
+  }));
+
+  env.define("type_of!", values::RuntimeValue::native("type_of!", Arity::Exact(1), |args, _| {
+    match args.first() {
+      Some(value) => Ok(values::RuntimeValue::String { value: value.type_name() }),
+      None => throw!(ErrorKind::Argument, "type_of! expects a value")
     }
-  }, false);
-
-  env.declare_var("Date".to_string(), values::RuntimeValue::Object(values::Object {
-    properties: {
-      let mut map = HashMap::new();
-      map.insert("now".to_string(), values::RuntimeValue::NativeFunction {
-        body: |_, _| {
-          let start = std::time::SystemTime::now();
-          let since_the_epoch = start.duration_since(std::time::UNIX_EPOCH).expect("Time went backwards");
-          values::RuntimeValue::Number { value: since_the_epoch.as_secs_f32() }
-        }
-      });
-      map
+  }));
+
+  env.define("iter!", values::RuntimeValue::native("iter!", Arity::Exact(1), |args, _| {
+    match args.first() {
+      Some(value) => Ok(values::RuntimeValue::Iterator(iteration::iterate(value)?)),
+      None => throw!(ErrorKind::Argument, "iter! expects a value to iterate over")
     }
-  }), false);
-
-  env.declare_var("Array".to_string(), values::RuntimeValue::Object(values:: Object {
-    properties: {
-      let mut map = HashMap::new();
-      map.insert("new".to_string(), values::RuntimeValue::NativeFunction {
-        body: |args, _| {
-          let amount = match args.get(0) {
-            Some(values::RuntimeValue::Number { value }) => *value as usize,
-            _ => 0
-          };
-          let mut elements = Vec::new();
-          for _ in 0..amount {
-            elements.push(values::RuntimeValue::Null);
-          }
-          values::RuntimeValue::Array { elements }
-        }
-      });
-      map.insert("from".to_string(), values::RuntimeValue::NativeFunction {
-        body: |args, _| {
-          let mut elements = Vec::new();
-          for arg in args {
-            elements.push(arg);
-          }
-          values::RuntimeValue::Array { elements }
-        }
-      });
-      map.insert("has".to_string(), values::RuntimeValue::NativeFunction {
-        body: |args, _| {
-          let array = match args.get(0) {
-            Some(values::RuntimeValue::Array { elements }) => elements,
-            _ => panic!("First argument must be an array")
-          };
-          let value = match args.get(1) {
-            Some(value) => value,
-            _ => panic!("Second argument must be a value")
-          };
-          let mut has = false;
-          for element in array {
-            if element == value {
-              has = true;
-              break;
-            }
+  }));
+
+  env.define("same!", values::RuntimeValue::native("same!", Arity::Exact(2), |args, _| {
+    match (args.first(), args.get(1)) {
+      (Some(lhs), Some(rhs)) => Ok(values::RuntimeValue::Bool { value: lhs.is_same(rhs) }),
+      _ => throw!(ErrorKind::Argument, "same! expects two arguments")
+    }
+  }));
+
+  env.define("clone!", values::RuntimeValue::native("clone!", Arity::Exact(1), |args, _| {
+    match args.first() {
+      Some(value) => Ok(value.shallow_copy()),
+      None => throw!(ErrorKind::Argument, "clone! expects a value to copy")
+    }
+  }));
+
+  env.define("gc!", values::RuntimeValue::native("gc!", Arity::Exact(0), |_, _| Ok(gc_stats_object(values::heap::collect()))));
+
+  env.define("gc_stats!", values::RuntimeValue::native("gc_stats!", Arity::Exact(0), |_, _| Ok(gc_stats_object(values::heap::stats()))));
+
+  env.define("gc_config!", values::RuntimeValue::native("gc_config!", Arity::Between(0, 1), |args, _| {
+    let mut config = values::heap::config();
+    match args.first() {
+      Some(values::RuntimeValue::Object(options)) => {
+        for (key, value) in options.borrow().properties.iter() {
+          match (key.as_str(), value) {
+            ("enabled", values::RuntimeValue::Bool { value }) => config.enabled = *value,
+            ("threshold", values::RuntimeValue::Number { value }) if *value >= 1.0 => config.threshold = *value as usize,
+            ("growth", values::RuntimeValue::Number { value }) if *value >= 1.0 => config.growth = *value,
+            _ => throw!(ErrorKind::Argument, "Invalid gc option {}: {:?}", key, value)
           }
-          values::RuntimeValue::Bool { value: has }
         }
-      });
-      map.insert("concat".to_string(), values::RuntimeValue::NativeFunction {
-        body: |args, _| {
-          let array = match args.get(0) {
-            Some(values::RuntimeValue::Array { elements }) => elements,
-            _ => panic!("First argument must be an array")
-          };
-          let value = match args.get(1) {
-            Some(value) => value,
-            _ => panic!("Second argument must be a value")
-          };
-          let mut elements = array.clone();
-          elements.push(value.clone());
-          values::RuntimeValue::Array { elements }
-        }
-      });
-      map
+        values::heap::configure(config);
+      },
+      None => (),
+      _ => throw!(ErrorKind::Argument, "gc_config! expects an object of options")
     }
-  }), false);
+    let mut map = HashMap::new();
+    map.insert("enabled".to_string(), values::RuntimeValue::Bool { value: config.enabled });
+    map.insert("threshold".to_string(), values::RuntimeValue::Number { value: config.threshold as f32 });
+    map.insert("growth".to_string(), values::RuntimeValue::Number { value: config.growth });
+    Ok(values::RuntimeValue::object(map))
+  }));
+
+  env.define("Date", values::RuntimeValue::object({
+    let mut map = HashMap::new();
+    map.insert("now".to_string(), values::RuntimeValue::native("Date::now", Arity::Exact(0), |_, _| {
+      let start = std::time::SystemTime::now();
+      let since_the_epoch = start.duration_since(std::time::UNIX_EPOCH).expect("Time went backwards");
+      Ok(values::RuntimeValue::Number { value: since_the_epoch.as_secs_f32() })
+    }));
+    map
+  }));
+
+  env.define("Math", math::math_object());
+  env.define("String", strings::string_object());
+
+  // reading and writing files can fail for reasons the script can't rule out
+  // beforehand, so these return a Result
+  env.define("File", values::RuntimeValue::object({
+    let mut map = HashMap::new();
+    map.insert("read".to_string(), values::RuntimeValue::native("File::read", Arity::Exact(1), |args, _| {
+      let Some(values::RuntimeValue::String { value: path }) = args.first() else {
+        throw!(ErrorKind::Argument, "File::read expects a path");
+      };
+      Ok(match std::fs::read_to_string(path) {
+        Ok(text) => results::ok(values::RuntimeValue::String { value: text }),
+        Err(err) => results::err(io_error(path, err))
+      })
+    }));
+    map.insert("write".to_string(), values::RuntimeValue::native("File::write", Arity::Exact(2), |args, _| {
+      let (Some(values::RuntimeValue::String { value: path }), Some(text)) = (args.first(), args.get(1)) else {
+        throw!(ErrorKind::Argument, "File::write expects a path and the text to write");
+      };
+      Ok(match std::fs::write(path, text.to_string()) {
+        Ok(()) => results::ok(values::RuntimeValue::Null),
+        Err(err) => results::err(io_error(path, err))
+      })
+    }));
+    map
+  }));
+
+  env.define("Number", values::RuntimeValue::object({
+    let mut map = HashMap::new();
+    map.insert("parse".to_string(), values::RuntimeValue::native("Number::parse", Arity::Exact(1), |args, _| {
+      let Some(values::RuntimeValue::String { value: text }) = args.first() else {
+        throw!(ErrorKind::Argument, "Number::parse expects a string");
+      };
+      Ok(strings::parse_number(text))
+    }));
+    map
+  }));
+
+  env.define("Array", arrays::array_object());
+  env.define("Object", objects::object_object());
+
+  REGISTERED.with(|registered| {
+    for (name, value) in registered.borrow().iter() {
+      env.define(name, value.clone());
+    }
+  });
 
   env
 }
 
-#[derive(Debug, Clone, PartialEq)]
+fn format_native_args(name: &str, args: &[values::RuntimeValue]) -> Eval<String> {
+  match args.split_first() {
+    Some((values::RuntimeValue::String { value: template }, args)) => {
+      match format::format_args(template, args) {
+        Ok(text) => Ok(text),
+        Err(err) => throw!(ErrorKind::Value, "{}: {}", name, err)
+      }
+    },
+    _ => throw!(ErrorKind::Argument, "{} expects a format string as first argument", name)
+  }
+}
+
+fn io_error(path: &str, err: std::io::Error) -> values::RuntimeValue {
+  errors::error_value(ErrorKind::IO.to_string(), format!("{}: {}", path, err), values::RuntimeValue::Null)
+}
+
+fn gc_stats_object(stats: values::heap::GcStats) -> values::RuntimeValue {
+  let mut map = HashMap::new();
+  for (key, value) in [
+    ("collections", stats.collections),
+    ("allocations", stats.allocations),
+    ("live", stats.live),
+    ("freed", stats.freed),
+    ("last_freed", stats.last_freed),
+    ("next_collection", stats.next_collection)
+  ] {
+    map.insert(key.to_string(), values::RuntimeValue::Number { value: value as f32 });
+  }
+  values::RuntimeValue::object(map)
+}
+
+// An environment is a handle to a scope on the heap. Cloning it shares the scope,
+// which is how closures keep their declaring scope alive.
+#[derive(Clone)]
 pub struct Environment {
-  parent: Option<Box<Environment>>,
+  scope: Rc<RefCell<Scope>>
+}
+
+pub struct Scope {
+  parent: Option<Environment>,
   variables: HashMap<String, values::RuntimeValue>,
   constants: HashSet<String>
 }
 
 impl Environment {
   pub fn new(parent_env: Option<Environment>) -> Environment {
-    let mut env = Environment {
-      parent: None,
+    let scope = Rc::new(RefCell::new(Scope {
+      parent: parent_env,
       variables: HashMap::new(),
       constants: HashSet::new()
-    };
-    if let Some(parent) = parent_env {
-      env.parent = Some(Box::new(parent));
-    }
-    env
+    }));
+    values::heap::register(Rc::downgrade(&scope) as Weak<dyn Trace>);
+    Environment { scope }
   }
 
-  pub fn declare_var(&mut self, name: String, value: values::RuntimeValue, mutable: bool) -> values::RuntimeValue {
-    if self.variables.contains_key(&name) {
-      panic!("Variable {} already declared", name);
+  pub fn declare_var(&mut self, name: String, value: values::RuntimeValue, mutable: bool) -> Eval {
+    let mut scope = self.scope.borrow_mut();
+    if scope.variables.contains_key(&name) {
+      throw!(ErrorKind::Reference, "Variable {} already declared", name);
     }
     if !mutable  {
-      self.constants.insert(name.clone());
+      scope.constants.insert(name.clone());
     }
-    self.variables.insert(name, value.clone());
-    value
+    scope.variables.insert(name, value.clone());
+    Ok(value)
   }
 
-  pub fn void_var(&mut self, name: String) -> values::RuntimeValue {
-    if self.variables.contains_key(&name) {
-      self.variables.remove(&name.clone());
+  // Declares a constant in a scope that is still being set up, so the name can't be taken.
+  fn define(&mut self, name: &str, value: values::RuntimeValue) {
+    let mut scope = self.scope.borrow_mut();
+    scope.constants.insert(name.to_string());
+    scope.variables.insert(name.to_string(), value);
+  }
+
+  pub fn assign_var(&mut self, name: String, value: &values::RuntimeValue) -> Eval {
+    let env = self.resolve(name.clone())?;
+    let mut scope = env.scope.borrow_mut();
+    if scope.constants.contains(&name) {
+      throw!(ErrorKind::Reference, "Cannot assign to constant {}", name);
     }
-    values::RuntimeValue::Null
+    scope.variables.insert(name, (*value).clone());
+    Ok(value.clone())
   }
 
-  pub fn assign_var(&mut self, name: String, value: &values::RuntimeValue) -> values::RuntimeValue {
-    let env = self.resolve(name.clone());
-    if env.constants.contains(&name) {
-      panic!("Cannot assign to constant {}", name);
+  pub fn lookup_var(&mut self, name: String) -> Eval {
+    match self.try_lookup_var(&name) {
+      Some(value) => Ok(value),
+      None => throw!(ErrorKind::Reference, "Variable {} is not defined", name)
     }
-    env.variables.insert(name, (*value).clone());
-    value.clone()
   }
 
-  pub fn lookup_object_member(&mut self, object: Option<Box<Expr>>, val: Option<RuntimeValue>, property: Box<Expr>, computed: bool) -> values::RuntimeValue {
-    // values::RuntimeValue::Null
-    /*
-    Member { 
-      object: Member { 
-        object: Ident { symbol: "foo" }, 
-        property: Ident { symbol: "bar" }, 
-        computed: false 
-      }, property: Ident { symbol: "complex" }, 
-    computed: false }
-    */
-    println!("{:?}", object);
-    println!("{:?}", property);
-    match *object.clone().unwrap() {
-      Expr::Ident { symbol } => {
-        let env = self.resolve(symbol.clone());
-        let past_val = env.lookup_var(symbol.clone());
-
-        let obj = match past_val {
-          values::RuntimeValue::Object(obj) => obj,
-          _ => panic!("{} is not an object", symbol)
-        };
-        let prop = match *property {
-          Expr::Ident { symbol } => symbol,
-          _ => panic!("{:?} is not a valid property", property)
-        };
-        if obj.properties.contains_key(&prop) {
-          obj.properties.get(&prop).unwrap().clone()
-        } else {
-          values::RuntimeValue::Null
-        }
-      },
-      Expr::Member { object: o, property: p, computed: c } => self.lookup_object_member(
-        Some(o),
-        None, 
-        p, 
-        c
-      ),
-      _ => panic!("Not implemented")
+  pub fn try_lookup_var(&self, name: &str) -> Option<values::RuntimeValue> {
+    let scope = self.scope.borrow();
+    match scope.variables.get(name) {
+      Some(value) => Some(value.clone()),
+      None => scope.parent.as_ref().and_then(|parent| parent.try_lookup_var(name))
     }
+  }
 
+  pub fn resolve(&mut self, varname: String) -> Eval<Environment> {
+    if self.scope.borrow().variables.contains_key(&varname) {
+      return Ok(self.clone())
+    }
+    let parent = self.scope.borrow().parent.clone();
+    match parent {
+      Some(mut parent) => parent.resolve(varname),
+      None => throw!(ErrorKind::Reference, "Variable {} is not defined", varname)
+    }
   }
 
+  pub fn heap_ptr(&self) -> *const () {
+    Rc::as_ptr(&self.scope) as *const ()
+  }
+}
 
+impl PartialEq for Environment {
+  fn eq(&self, other: &Environment) -> bool {
+    Rc::ptr_eq(&self.scope, &other.scope)
+  }
+}
 
-  pub fn lookup_var(&mut self, name: String) -> values::RuntimeValue {
-    let env = self.resolve(name.clone());
-    match env.variables.get(&name) {
-      Some(value) => (*value).clone(),
-      None => panic!("Variable {} is not defined", name)
-    }
+// Scopes refer back to the functions declared in them, so printing one in full
+// would never end.
+impl fmt::Debug for Environment {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Environment").finish_non_exhaustive()
   }
+}
 
-  pub fn resolve(&mut self, varname: String) -> &mut Environment {
-    if self.variables.contains_key(&varname) {
-      return self
+impl Trace for RefCell<Scope> {
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) -> bool {
+    let Ok(scope) = self.try_borrow() else {
+      return false;
+    };
+    if let Some(parent) = &scope.parent {
+      visit(parent.heap_ptr());
     }
-    match self.parent {
-      Some(ref mut parent) => parent.resolve(varname),
-      None => panic!("Variable {} is not defined", varname)
+    for value in scope.variables.values() {
+      value.trace(visit);
     }
+    true
+  }
+
+  fn clear(&self) {
+    let mut scope = self.borrow_mut();
+    scope.parent = None;
+    scope.variables.clear();
   }
-}
\ No newline at end of file
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::values::{Arity, RuntimeValue, StructType};
+
+// Evaluation either gives a value or is interrupted, by a `ret` that unwinds to the
+// function being returned from, or by a thrown value that unwinds to the nearest
+// `try`. Both travel through the `Err` side so `?` takes care of the unwinding.
+#[derive(Debug)]
+pub enum Interrupt {
+  Return(RuntimeValue),
+  Throw(RuntimeValue)
+}
+
+pub type Eval<T = RuntimeValue> = Result<T, Interrupt>;
+
+// The kinds of the errors the interpreter and the natives raise. Scripts may use
+// any string as kind for their own errors.
+#[derive(Debug, Clone, Copy)]
+pub enum ErrorKind {
+  // an operation got a value of the wrong type, like calling a number
+  Type,
+  // a variable that isn't declared, or declared twice
+  Reference,
+  // a native or function got the wrong number or kind of arguments
+  Argument,
+  // an index or key that isn't there
+  Index,
+  // a value that doesn't fit a pattern, or no `match` arm matched
+  Match,
+  // a value of the right type that still can't be used, like a step of 0
+  Value,
+  // reading or writing a file failed
+  IO,
+  // a module that can't be found, imports itself or lacks an imported name
+  Import
+}
+
+impl fmt::Display for ErrorKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let name = match self {
+      ErrorKind::Type => "TypeError",
+      ErrorKind::Reference => "ReferenceError",
+      ErrorKind::Argument => "ArgumentError",
+      ErrorKind::Index => "IndexError",
+      ErrorKind::Match => "MatchError",
+      ErrorKind::Value => "ValueError",
+      ErrorKind::IO => "IOError",
+      ErrorKind::Import => "ImportError"
+    };
+    write!(f, "{}", name)
+  }
+}
+
+// `throw!(ErrorKind::Type, "Cannot call {}", name)` returns an error from the
+// enclosing function, or closure.
+macro_rules! throw {
+  ($kind:expr, $($arg:tt)*) => {
+    return Err($crate::runtime::errors::error($kind, format!($($arg)*)))
+  };
+}
+pub(crate) use throw;
+
+thread_local! {
+  static ERROR_TYPE: Rc<StructType> = {
+    let RuntimeValue::StructType(error_type) = RuntimeValue::struct_type(
+      "Error".to_string(),
+      ["message", "kind", "location", "cause"].iter().map(|field| field.to_string()).collect()
+    ) else {
+      unreachable!()
+    };
+    error_type.methods.borrow_mut().insert("new".to_string(), RuntimeValue::native("Error::new", Arity::Between(1, 3), new_error));
+    error_type
+  };
+}
+
+// `Error::new(message)`, `Error::new(message, kind)` or `Error::new(message, kind, cause)`,
+// the kind is "Error" when it is left out or null.
+fn new_error(args: Vec<RuntimeValue>, _: &mut Interpreter) -> Eval {
+  let mut args = args.into_iter();
+  let message = match args.next() {
+    Some(RuntimeValue::String { value }) => value,
+    _ => throw!(ErrorKind::Argument, "Error::new expects a message string")
+  };
+  let kind = match args.next() {
+    Some(RuntimeValue::String { value }) => value,
+    None | Some(RuntimeValue::Null) => "Error".to_string(),
+    Some(other) => throw!(ErrorKind::Argument, "The kind of an error has to be a string, not {:?}", other)
+  };
+  let cause = args.next().unwrap_or(RuntimeValue::Null);
+  Ok(error_value(kind, message, cause))
+}
+
+// The built-in `Error` struct. `location` is filled in with the line of the
+// statement the error is thrown from, unless it was given.
+pub fn error_type() -> Rc<StructType> {
+  ERROR_TYPE.with(|error_type| error_type.clone())
+}
+
+pub fn error_value(kind: String, message: String, cause: RuntimeValue) -> RuntimeValue {
+  RuntimeValue::struct_value(error_type(), vec![
+    RuntimeValue::String { value: message },
+    RuntimeValue::String { value: kind },
+    RuntimeValue::Null,
+    cause
+  ])
+}
+
+pub fn error(kind: ErrorKind, message: String) -> Interrupt {
+  Interrupt::Throw(error_value(kind.to_string(), message, RuntimeValue::Null))
+}
+
+pub fn error_with_cause(kind: ErrorKind, message: String, cause: RuntimeValue) -> Interrupt {
+  Interrupt::Throw(error_value(kind.to_string(), message, cause))
+}
+
+// Gives a thrown error without a location the line it passes through first, which
+// is the line of the statement that threw it.
+pub fn locate(interrupt: Interrupt, line: usize) -> Interrupt {
+  if let Interrupt::Throw(RuntimeValue::Struct(value)) = &interrupt {
+    let mut value = value.borrow_mut();
+    if Rc::ptr_eq(&value.struct_type, &error_type()) && value.fields[2] == RuntimeValue::Null {
+      value.fields[2] = RuntimeValue::String { value: format!("line {}", line) };
+    }
+  }
+  interrupt
+}
+
+// How an error that no `try` caught is reported, with the errors that caused it. A
+// chain of causes that leads back to an error already reported ends in "(cycle)".
+pub fn describe_uncaught(value: &RuntimeValue) -> String {
+  let mut text = format!("Uncaught {}", describe(value));
+  let mut seen = vec![value.clone()];
+  let mut cause = cause_of(value);
+  while let Some(value) = cause {
+    if seen.iter().any(|earlier| earlier.is_same(&value)) {
+      text.push_str("\n  caused by (cycle)");
+      break;
+    }
+    text.push_str(&format!("\n  caused by {}", describe(&value)));
+    cause = cause_of(&value);
+    seen.push(value);
+  }
+  text
+}
+
+fn describe(value: &RuntimeValue) -> String {
+  let RuntimeValue::Struct(error) = value else {
+    return format!("{:?}", value);
+  };
+  let error = error.borrow();
+  if !Rc::ptr_eq(&error.struct_type, &error_type()) {
+    return format!("{:?}", value);
+  }
+  match &error.fields[2] {
+    RuntimeValue::Null => format!("{}: {}", error.fields[1], error.fields[0]),
+    location => format!("{} at {}: {}", error.fields[1], location, error.fields[0])
+  }
+}
+
+fn cause_of(value: &RuntimeValue) -> Option<RuntimeValue> {
+  let RuntimeValue::Struct(error) = value else {
+    return None;
+  };
+  let error = error.borrow();
+  match &error.fields[3] {
+    RuntimeValue::Null => None,
+    cause if Rc::ptr_eq(&error.struct_type, &error_type()) => Some(cause.clone()),
+    _ => None
+  }
+}
@@ -1,166 +1,592 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
 use crate::runtime::environment::Environment;
-use crate::runtime::values::{RuntimeValue, Object};
-use crate::frontend::ast::{Expr, Property, Stmt};
+use crate::runtime::errors::{throw, ErrorKind, Eval, Interrupt};
+use crate::runtime::values::{Arity, RuntimeValue};
+use crate::runtime::values::range::Range;
+use crate::runtime::values::compare::{compare, equals};
+use crate::runtime::methods;
+use crate::runtime::results;
+use crate::runtime::evaluate::statements::{evaluate_block, evaluate_branch, evaluate_if_stmt};
+use crate::runtime::evaluate::generator::Generator;
+use crate::runtime::evaluate::patterns::{declare_pattern, match_pattern};
+use crate::frontend::ast::{self, contains_yield, Expr, MatchArm, Param, Pattern, Property, Stmt};
+use crate::runtime::iteration::iterate;
+use crate::runtime::interpreter::Interpreter;
 
-pub fn evaluate_expr(node: Expr, env: &mut Environment) -> RuntimeValue {
+// `name: value` arguments, in the order they are written.
+pub type NamedArgs = Vec<(String, RuntimeValue)>;
+
+pub fn evaluate_expr(node: Expr, env: &mut Environment) -> Eval {
   match node {
-    Expr::IntLit { value } => RuntimeValue::Number { value },
+    Expr::IntLit { value } => Ok(RuntimeValue::Number { value }),
     Expr::Array { elements } => {
-      let mut array = Vec::new();
-      for element in elements {
-        array.push(evaluate_expr(element, env));
-      }
-      RuntimeValue::Array { elements: array }
+      let (array, _) = evaluate_args(elements, env)?;
+      Ok(RuntimeValue::array(array))
     },
     Expr::BinExp { left, op, right } => evaluate_binary_expr(*left, op, *right, env),
     Expr::Ident { symbol } => evaluate_ident(symbol, env),
     Expr::Assign { assignee, value } => evaluate_assignment(*assignee, *value, env),
+    Expr::CompoundAssign { assignee, op, value } => evaluate_compound_assignment(*assignee, op, *value, env),
     Expr::ObjectLit { properties } => evaluate_object_expr(properties, env),
-    Expr::Call { callee, args } => evaluate_call_expr(callee, args, env),
-    Expr::Member { object: _, property: _, computed: _ } => evaluate_member_expr(node, env),
-    Expr::String { value } => RuntimeValue::String { value },
-    _ => panic!("Not implemented {:?}", node)//values::RuntimeValue::Null
-  }
-}
-
-pub fn evaluate_binary_expr(left: Expr, op: String, right: Expr, env: &mut Environment) -> RuntimeValue {
-  let lhs = evaluate_expr(left, env);
-  let rhs = evaluate_expr(right, env);
-
-  match (lhs, rhs) {
-    (RuntimeValue::Number { value: lhs }, RuntimeValue::Number { value: rhs }) => {
-      match op.as_str() {
-        "+" => RuntimeValue::Number { value: lhs + rhs },
-        "-" => RuntimeValue::Number { value: lhs - rhs },
-        "*" => RuntimeValue::Number { value: lhs * rhs },
-        "/" => RuntimeValue::Number { value: lhs / rhs },
-        "%" => RuntimeValue::Number { value: lhs % rhs },
-        "==" => RuntimeValue::Bool { value: equals(RuntimeValue::Number { value: lhs }, RuntimeValue::Number { value: rhs }, true) },
-        "!=" => RuntimeValue::Bool { value: equals(RuntimeValue::Number { value: lhs }, RuntimeValue::Number { value: rhs }, false) },
-        "<" => RuntimeValue::Bool { value: lhs < rhs },
-        ">" => RuntimeValue::Bool { value: lhs > rhs },
-        _ => RuntimeValue::Null
-      }
-    },
-    (RuntimeValue::String { value: lhs }, RuntimeValue::String { value: rhs }) => {
-      match op.as_str() {
-        "+" => RuntimeValue::String { value: lhs + &rhs },
-        "==" => RuntimeValue::Bool { value: equals(RuntimeValue::String { value: lhs }, RuntimeValue::String { value: rhs }, true) },
-        "!=" => RuntimeValue::Bool { value: equals(RuntimeValue::String { value: lhs }, RuntimeValue::String { value: rhs }, false) },
-        _ => RuntimeValue::Null
-      }
-    },
-    (RuntimeValue::Bool { value: lhs }, RuntimeValue::Bool { value: rhs }) => {
-      match op.as_str() {
-        "==" => RuntimeValue::Bool { value: equals(RuntimeValue::Bool { value: lhs }, RuntimeValue::Bool { value: rhs }, true) },
-        "!=" => RuntimeValue::Bool { value: equals(RuntimeValue::Bool { value: lhs }, RuntimeValue::Bool { value: rhs }, false) },
-        _ => RuntimeValue::Null
-      }
-    },
-    (lhs, RuntimeValue::Array { elements }) => {
-      match op.as_str() {
-        "in" => {
-          let index = elements.iter().position(|x| *x == lhs);
-          if index.is_none() {
-            RuntimeValue::Null
-          } else {
-            elements[index.unwrap()].clone()
-          }
-        },
-        _ => RuntimeValue::Null
+    Expr::StructLit { name, properties } => evaluate_struct_expr(name, properties, env),
+    Expr::Call { callee, args } => evaluate_call_expr(*callee, args, env),
+    Expr::MethodCall { object, method, args } => evaluate_method_call(*object, method, args, env),
+    Expr::Member { object, property, computed } => evaluate_member_expr(*object, *property, computed, env),
+    Expr::String { value } => Ok(RuntimeValue::String { value }),
+    Expr::Match { subject, arms } => evaluate_match_expr(*subject, arms, env),
+    Expr::If { condition, then_branch, else_branch } => evaluate_if_stmt(*condition, then_branch, else_branch, env),
+    Expr::Block { body } => evaluate_branch(body, env),
+    Expr::Range { start, end, inclusive } => evaluate_range_expr(start.map(|start| *start), end.map(|end| *end), inclusive, env),
+    Expr::Spread { .. } => throw!(ErrorKind::Type, "'...' can only spread values into an array or the arguments of a call, or collect the rest of an array in a destructuring assignment"),
+    Expr::NamedArg { name, .. } => throw!(ErrorKind::Type, "The named argument {} can only be passed to a function", name),
+    Expr::Propagate { expr } => evaluate_propagate(*expr, env),
+  }
+}
+
+pub fn evaluate_binary_expr(left: Expr, op: String, right: Expr, env: &mut Environment) -> Eval {
+  let lhs = evaluate_expr(left, env)?;
+  let rhs = evaluate_expr(right, env)?;
+  apply_binary_op(lhs, &op, rhs)
+}
+
+// Operands of types the operator isn't defined for are a TypeError.
+fn apply_binary_op(lhs: RuntimeValue, op: &str, rhs: RuntimeValue) -> Eval {
+  match op {
+    "==" => return Ok(RuntimeValue::Bool { value: equals(&lhs, &rhs) }),
+    "!=" => return Ok(RuntimeValue::Bool { value: !equals(&lhs, &rhs) }),
+    "<" | ">" | "<=" | ">=" => {
+      return match compare(&lhs, &rhs) {
+        Some(ordering) => Ok(RuntimeValue::Bool { value: match op {
+          "<" => ordering == Ordering::Less,
+          ">" => ordering == Ordering::Greater,
+          "<=" => ordering != Ordering::Greater,
+          _ => ordering != Ordering::Less
+        } }),
+        None => throw!(ErrorKind::Type, "Can't apply '{}' to {} and {}", op, lhs.type_name(), rhs.type_name())
+      };
+    },
+    _ => ()
+  }
+
+  match (&lhs, &rhs) {
+    (RuntimeValue::Number { value: lhs }, RuntimeValue::Number { value: rhs }) if ["+", "-", "*", "/", "%"].contains(&op) => {
+      Ok(RuntimeValue::Number { value: match op {
+        "+" => lhs + rhs,
+        "-" => lhs - rhs,
+        "*" => lhs * rhs,
+        "/" => lhs / rhs,
+        _ => lhs % rhs
+      } })
+    },
+    (RuntimeValue::String { .. }, _) | (_, RuntimeValue::String { .. }) if op == "+" => {
+      Ok(RuntimeValue::String { value: format!("{}{}", lhs, rhs) })
+    },
+    (_, RuntimeValue::Range(range)) if op == "in" => {
+      Ok(RuntimeValue::Bool { value: matches!(lhs, RuntimeValue::Number { value } if range.contains(value)) })
+    },
+    (_, RuntimeValue::Array { elements }) if op == "in" => {
+      let elements = elements.borrow();
+      match elements.iter().position(|element| equals(element, &lhs)) {
+        Some(index) => Ok(elements[index].clone()),
+        None => Ok(RuntimeValue::Null)
       }
     },
-    _ => RuntimeValue::Null
+    _ => throw!(ErrorKind::Type, "Can't apply '{}' to {} and {}", op, lhs.type_name(), rhs.type_name())
+  }
+}
+
+pub fn evaluate_range_expr(start: Option<Expr>, end: Option<Expr>, inclusive: bool, env: &mut Environment) -> Eval {
+  let mut bound = |expr: Option<Expr>| -> Eval<Option<f32>> {
+    let Some(expr) = expr else {
+      return Ok(None);
+    };
+    // an infinite end would make a range that never stops, leave the end out for that
+    match evaluate_expr(expr, env)? {
+      RuntimeValue::Number { value } if value.is_finite() => Ok(Some(value)),
+      RuntimeValue::Number { value } => throw!(ErrorKind::Value, "The bounds of a range have to be finite, not {}", RuntimeValue::Number { value }),
+      other => throw!(ErrorKind::Type, "A range can only be made of numbers, not {:?}", other)
+    }
+  };
+  let start = bound(start)?;
+  let end = bound(end)?;
+  Ok(RuntimeValue::Range(Range { start, end, inclusive, step: 1.0 }))
+}
+
+// `Some(v)?` and `Ok(v)?` are `v`, `None` and `Err(e)` are returned from the function
+// the `?` is in, like a `ret`.
+pub fn evaluate_propagate(expr: Expr, env: &mut Environment) -> Eval {
+  let value = evaluate_expr(expr, env)?;
+  match results::split(&value) {
+    Some(Ok(inner)) => Ok(inner),
+    Some(Err(_)) => Err(Interrupt::Return(value)),
+    None => throw!(ErrorKind::Type, "'?' expects an Option or a Result, not {:?}", value)
   }
 }
 
-pub fn evaluate_ident(symbol: String, env: &mut Environment) -> RuntimeValue {
+pub fn evaluate_ident(symbol: String, env: &mut Environment) -> Eval {
   env.lookup_var(symbol)
 }
 
-pub fn evaluate_object_expr(properties: Vec<Property>, env: &mut Environment) -> RuntimeValue {
-  let mut object = Object { properties: HashMap::new() };
+pub fn evaluate_object_expr(properties: Vec<Property>, env: &mut Environment) -> Eval {
+  let mut object = HashMap::new();
 
   for prop in properties {
     let runtime_val = match prop.value {
-      None => env.lookup_var(prop.key.clone()),
-      Some(expr) => evaluate_expr(*expr, env)
+      None => env.lookup_var(prop.key.clone())?,
+      Some(expr) => evaluate_expr(*expr, env)?
     };
-    object.properties.insert(prop.key, runtime_val);
+    object.insert(prop.key, runtime_val);
   }
 
-  RuntimeValue::Object(object)
+  Ok(RuntimeValue::object(object))
+}
+
+// Every field has to be given exactly once, `Point { x, y }` takes them from variables.
+pub fn evaluate_struct_expr(name: String, properties: Vec<Property>, env: &mut Environment) -> Eval {
+  let struct_type = match env.lookup_var(name.clone())? {
+    RuntimeValue::StructType(struct_type) => struct_type,
+    other => throw!(ErrorKind::Type, "{} is a {}, not a struct", name, other.type_name())
+  };
+  let mut fields: Vec<Option<RuntimeValue>> = vec![None; struct_type.fields.len()];
+  for prop in properties {
+    let Some(index) = struct_type.field_index(&prop.key) else {
+      throw!(ErrorKind::Type, "Struct {} has no field {}", name, prop.key);
+    };
+    if fields[index].is_some() {
+      throw!(ErrorKind::Type, "Field {} of {} is given twice", prop.key, name);
+    }
+    fields[index] = Some(match prop.value {
+      None => env.lookup_var(prop.key.clone())?,
+      Some(expr) => evaluate_expr(*expr, env)?
+    });
+  }
+  let mut values = Vec::new();
+  for (i, field) in fields.into_iter().enumerate() {
+    match field {
+      Some(field) => values.push(field),
+      None => throw!(ErrorKind::Type, "Missing field {} in {} literal", struct_type.fields[i], name)
+    }
+  }
+  Ok(RuntimeValue::struct_value(struct_type, values))
 }
 
-pub fn evaluate_assignment(assignee: Expr, value: Expr, env: &mut Environment) -> RuntimeValue {
+pub fn evaluate_assignment(assignee: Expr, value: Expr, env: &mut Environment) -> Eval {
   match assignee {
-    Expr::Member { object: _, property: _, computed: _ } => evaluate_member_expr(assignee, env),
+    Expr::Member { object, property, computed } => {
+      let object = evaluate_expr(*object, env)?;
+      let key = evaluate_member_key(*property, computed, env)?;
+      let res = evaluate_expr(value, env)?;
+      assign_member(&object, &key, res.clone())?;
+      Ok(res)
+    },
     Expr::Ident { symbol } => {
-      let res = evaluate_expr(value, env);
+      let res = evaluate_expr(value, env)?;
       env.assign_var(symbol, &res)
     },
-    _ => panic!("You can't assign to {:?}", assignee)
+    Expr::Array { .. } | Expr::ObjectLit { .. } => {
+      let res = evaluate_expr(value, env)?;
+      assign_destructured(assignee, res.clone(), env)?;
+      Ok(res)
+    },
+    _ => throw!(ErrorKind::Type, "You can't assign to {:?}", assignee)
+  }
+}
+
+// `total += x` is `total = total + x`, except that the target is evaluated only once,
+// so in `arr[next_index()] += 1` the index is computed once.
+pub fn evaluate_compound_assignment(assignee: Expr, op: String, value: Expr, env: &mut Environment) -> Eval {
+  match assignee {
+    Expr::Ident { symbol } => {
+      let current = env.lookup_var(symbol.clone())?;
+      let rhs = evaluate_expr(value, env)?;
+      env.assign_var(symbol, &apply_binary_op(current, &op, rhs)?)
+    },
+    Expr::Member { object, property, computed } => {
+      let object = evaluate_expr(*object, env)?;
+      let key = evaluate_member_key(*property, computed, env)?;
+      let current = member_of(&object, &key)?;
+      let rhs = evaluate_expr(value, env)?;
+      let res = apply_binary_op(current, &op, rhs)?;
+      assign_member(&object, &key, res.clone())?;
+      Ok(res)
+    },
+    _ => throw!(ErrorKind::Type, "You can't assign to {:?}", assignee)
   }
 }
 
-pub fn evaluate_call_expr(callee: Box<Expr>, args: Vec<Expr>, env: &mut Environment) -> RuntimeValue {
-  let runtime_args: Vec<RuntimeValue> = args.iter().map(|arg| evaluate_expr(arg.clone(), env)).collect();
-  let func = evaluate_expr(*callee, env);
+fn assign_member(object: &RuntimeValue, key: &RuntimeValue, res: RuntimeValue) -> Eval<()> {
+  match (object, key) {
+    (RuntimeValue::Object(object), RuntimeValue::String { value: key }) => {
+      let mut object = object.borrow_mut();
+      if object.frozen {
+        throw!(ErrorKind::Type, "Can't assign to {} of a frozen object", key);
+      }
+      object.properties.insert(key.clone(), res);
+    },
+    (RuntimeValue::Struct(value), RuntimeValue::String { value: key }) => {
+      let mut value = value.borrow_mut();
+      match value.struct_type.field_index(key) {
+        Some(index) => value.fields[index] = res,
+        None => throw!(ErrorKind::Type, "Struct {} has no field {}", value.struct_type.name, key)
+      }
+    },
+    (RuntimeValue::Array { elements }, RuntimeValue::Number { value: index }) => {
+      let mut elements = elements.borrow_mut();
+      match array_index(*index, elements.len()) {
+        Some(index) => elements[index] = res,
+        None => throw!(ErrorKind::Index, "Index {} out of bounds for array of length {}", index, elements.len())
+      }
+    },
+    _ => throw!(ErrorKind::Type, "You can't assign to {:?} of {:?}", key, object)
+  }
+  Ok(())
+}
 
-  match func {
-    RuntimeValue::NativeFunction { body } => body(runtime_args, env),
-    RuntimeValue::Function { name: _, params, decl_env, body } => {
-      let mut scope = Environment::new(Some(decl_env));
-      if params.len() != runtime_args.len() {
-        panic!("Wrong number of arguments passed to function");
-      }
-      for (i, arg) in runtime_args.iter().enumerate() {
-        scope.declare_var(params[i].clone(), arg.clone(), true);
-      }
-
-      let mut res: RuntimeValue = RuntimeValue::Null;
-      for stmt in body {
-        
-        match stmt {
-          Stmt::Return { value } => {
-            res = match value {
-              Some(expr) => evaluate_expr(expr, &mut scope),
-              None => RuntimeValue::Null
-            };
-            break;
+// `[a, b] = [b, a]` and `{ x, y: other::y } = point`. The value is evaluated in full
+// before anything is assigned, the targets can be variables, members or nested
+// arrays and objects, `_` skips a value.
+fn assign_destructured(target: Expr, value: RuntimeValue, env: &mut Environment) -> Eval<()> {
+  match target {
+    Expr::Ident { symbol } if symbol == "_" => (),
+    Expr::Ident { symbol } => {
+      env.assign_var(symbol, &value)?;
+    },
+    Expr::Member { object, property, computed } => {
+      let object = evaluate_expr(*object, env)?;
+      let key = evaluate_member_key(*property, computed, env)?;
+      assign_member(&object, &key, value)?;
+    },
+    Expr::Array { elements: targets } => {
+      let RuntimeValue::Array { elements } = &value else {
+        throw!(ErrorKind::Match, "Cannot destructure {:?} as an array", value);
+      };
+      let elements = elements.borrow().clone();
+      let rest = targets.iter().position(|target| matches!(target, Expr::Spread { .. }));
+      if targets.iter().filter(|target| matches!(target, Expr::Spread { .. })).count() > 1 {
+        throw!(ErrorKind::Match, "Only one '...' is allowed in a destructuring assignment");
+      }
+      let fits = match rest {
+        Some(_) => elements.len() >= targets.len() - 1,
+        None => elements.len() == targets.len()
+      };
+      if !fits {
+        throw!(
+          ErrorKind::Match,
+          "Cannot destructure an array of {} elements into {}{} targets", elements.len(),
+          if rest.is_some() { "at least " } else { "" }, targets.len() - rest.map_or(0, |_| 1)
+        );
+      }
+      let rest_len = elements.len() + 1 - targets.len();
+      let mut elements = elements.into_iter();
+      for target in targets {
+        match target {
+          Expr::Spread { expr } => {
+            let rest: Vec<RuntimeValue> = elements.by_ref().take(rest_len).collect();
+            assign_destructured(*expr, RuntimeValue::array(rest), env)?;
           },
-          Stmt::Expr(expr) => res = evaluate_expr(expr, &mut scope),
-          _ => res = RuntimeValue::Null
+          target => assign_destructured(target, elements.next().unwrap(), env)?
         }
       }
-      res
     },
-    _ => panic!("You can only call functions")
+    Expr::ObjectLit { properties } => {
+      for prop in properties {
+        let key = RuntimeValue::String { value: prop.key.clone() };
+        let property = match &value {
+          RuntimeValue::Object(object) => object.borrow().properties.get(&prop.key).cloned(),
+          RuntimeValue::Struct(_) => Some(member_of(&value, &key)?),
+          _ => throw!(ErrorKind::Match, "Cannot destructure {:?} as an object", value)
+        };
+        let Some(property) = property else {
+          throw!(ErrorKind::Match, "Cannot destructure {:?}, it has no key {}", value, prop.key);
+        };
+        let target = match prop.value {
+          Some(target) => *target,
+          None => Expr::Ident { symbol: prop.key }
+        };
+        assign_destructured(target, property, env)?;
+      }
+    },
+    target => throw!(ErrorKind::Type, "You can't assign to {:?}", target)
   }
+  Ok(())
 }
 
-pub fn evaluate_member_expr(node: Expr, env: &mut Environment) -> RuntimeValue {
-  // env.lookup_object_member(node)
-  match node {
-    Expr::Member { object, property, computed } => env.lookup_object_member(Some(object), None, property, computed),
-    Expr::Ident { symbol } => env.lookup_var(symbol),
-    _ => panic!("Oh oh")
+pub fn evaluate_call_expr(callee: Expr, args: Vec<Expr>, env: &mut Environment) -> Eval {
+  let (runtime_args, named) = evaluate_args(args, env)?;
+  let func = evaluate_expr(callee, env)?;
+  call_function_with(func, runtime_args, named, env)
+}
+
+// The positional and the named arguments, with `...iterable` spread into the
+// positional ones. Also used for the elements of array literals, which can't be named.
+fn evaluate_args(args: Vec<Expr>, env: &mut Environment) -> Eval<(Vec<RuntimeValue>, NamedArgs)> {
+  let mut positional = Vec::new();
+  let mut named = Vec::new();
+  for arg in args {
+    match arg {
+      Expr::Spread { expr } => {
+        let iterator = iterate(&evaluate_expr(*expr, env)?)?;
+        while let Some(value) = iterator.next(&mut Interpreter::new(env))? {
+          positional.push(value);
+        }
+      },
+      Expr::NamedArg { name, value } => named.push((name, evaluate_expr(*value, env)?)),
+      arg => positional.push(evaluate_expr(arg, env)?)
+    }
+  }
+  Ok((positional, named))
+}
+
+pub fn call_function(func: RuntimeValue, runtime_args: Vec<RuntimeValue>, env: &mut Environment) -> Eval {
+  call_function_with(func, runtime_args, Vec::new(), env)
+}
+
+// Natives get the named arguments as an object after the positional ones, the way
+// `format!` takes the values of named placeholders. Enum variants don't take any.
+pub fn call_function_with(func: RuntimeValue, mut runtime_args: Vec<RuntimeValue>, named: NamedArgs, env: &mut Environment) -> Eval {
+  if let (Some((name, _)), true) = (named.first(), matches!(func, RuntimeValue::Variant { .. })) {
+    throw!(ErrorKind::Argument, "{:?} doesn't take named arguments, like {}", func, name);
+  }
+  match func {
+    RuntimeValue::NativeFunction(native) => {
+      if !named.is_empty() {
+        let mut object = HashMap::new();
+        for (name, value) in named {
+          if object.insert(name.clone(), value).is_some() {
+            throw!(ErrorKind::Argument, "{} got two values for the named argument {}", native.name, name);
+          }
+        }
+        runtime_args.push(RuntimeValue::object(object));
+      }
+      if !native.arity.allows(runtime_args.len()) {
+        throw!(
+          ErrorKind::Argument, "{} takes {} but {} {} given", native.name, native.arity, runtime_args.len(),
+          if runtime_args.len() == 1 { "was" } else { "were" }
+        );
+      }
+      (native.body)(runtime_args, &mut Interpreter::new(env))
+    },
+    RuntimeValue::Function { name, params, decl_env, body } => {
+      let mut scope = Environment::new(Some(decl_env));
+      bind_params(&name, &params, runtime_args, named, &mut scope)?;
+      if contains_yield(&body) {
+        return Ok(RuntimeValue::iterator(Generator::new(body, scope)));
+      }
+
+      // a `ret` anywhere in the body unwinds to here
+      match evaluate_block(body, &mut scope) {
+        Err(Interrupt::Return(value)) => Ok(value),
+        res => res
+      }
+    },
+    RuntimeValue::Variant { enum_type, index } => {
+      let variant = &enum_type.variants[index];
+      if variant.fields.len() != runtime_args.len() {
+        throw!(
+          ErrorKind::Argument, "{}::{} takes {} value{} but {} {} given", enum_type.name, variant.name, variant.fields.len(),
+          if variant.fields.len() == 1 { "" } else { "s" }, runtime_args.len(), if runtime_args.len() == 1 { "was" } else { "were" }
+        );
+      }
+      Ok(RuntimeValue::enum_value(enum_type, index, runtime_args))
+    },
+    other => throw!(ErrorKind::Type, "You can only call functions, not {:?}", other)
+  }
+}
+
+// Declares the parameters in the scope of the call. Positional arguments go to the
+// parameters in order, named ones to the parameter of that name, and the arguments
+// left over to a `...rest` parameter. Parameters without an argument get their
+// default, evaluated in the scope of the call so it can use the parameters before it.
+fn bind_params(name: &str, params: &[Param], args: Vec<RuntimeValue>, named: NamedArgs, scope: &mut Environment) -> Eval<()> {
+  let signature = || format!("{}({})", name, ast::join(params));
+  let (params, rest) = match params.split_last() {
+    Some((rest @ Param { pattern: Pattern::Rest { .. }, .. }, params)) => (params, Some(rest)),
+    _ => (params, None)
+  };
+  let mut values: Vec<Option<RuntimeValue>> = vec![None; params.len()];
+  let mut args = args.into_iter();
+  for (value, arg) in values.iter_mut().zip(args.by_ref()) {
+    *value = Some(arg);
+  }
+  let extra: Vec<RuntimeValue> = args.collect();
+  if !extra.is_empty() && rest.is_none() {
+    let given = params.len() + extra.len();
+    throw!(
+      ErrorKind::Argument, "{} takes {} but {} {} given", signature(), Arity::Exact(params.len()), given,
+      if given == 1 { "was" } else { "were" }
+    );
+  }
+  for (arg, value) in named {
+    let Some(index) = params.iter().position(|param| matches!(&param.pattern, Pattern::Binding { name } if *name == arg)) else {
+      throw!(ErrorKind::Argument, "{} has no parameter named {}", signature(), arg);
+    };
+    if values[index].is_some() {
+      throw!(ErrorKind::Argument, "{} got two values for the parameter {}", signature(), arg);
+    }
+    values[index] = Some(value);
   }
+  for (param, value) in params.iter().zip(values) {
+    let value = match (value, &param.default) {
+      (Some(value), _) => value,
+      (None, Some(default)) => evaluate_expr(default.clone(), scope)?,
+      (None, None) => throw!(ErrorKind::Argument, "{} is missing the argument for {}", signature(), param.pattern)
+    };
+    declare_pattern(&param.pattern, value, true, scope)?;
+  }
+  if let Some(rest) = rest {
+    declare_pattern(&rest.pattern, RuntimeValue::array(extra), true, scope)?;
+  }
+  Ok(())
 }
 
-pub fn equals(lhs: RuntimeValue, rhs: RuntimeValue, value_if_eq: bool) -> bool {
-  let equals = match (lhs, rhs) {
-    (RuntimeValue::Number { value: lhs }, RuntimeValue::Number { value: rhs }) => lhs == rhs,
-    (RuntimeValue::String { value: lhs }, RuntimeValue::String { value: rhs }) => lhs == rhs,
-    (RuntimeValue::Bool { value: lhs }, RuntimeValue::Bool { value: rhs }) => lhs == rhs,
-    (RuntimeValue::Null, RuntimeValue::Null) => true,
-    _ => false
+pub fn evaluate_method_call(object: Expr, method: String, args: Vec<Expr>, env: &mut Environment) -> Eval {
+  let receiver = evaluate_expr(object, env)?;
+  let (mut runtime_args, named) = evaluate_args(args, env)?;
+
+  if let RuntimeValue::Struct(value) = &receiver {
+    let struct_type = value.borrow().struct_type.clone();
+    let func = struct_type.methods.borrow().get(&method).cloned();
+    return match func {
+      Some(func @ RuntimeValue::Function { .. }) if takes_self(&func) => {
+        runtime_args.insert(0, receiver);
+        call_function_with(func, runtime_args, named, env)
+      },
+      Some(_) => throw!(ErrorKind::Type, "{}::{} doesn't take self, call it as {}::{}(...)", struct_type.name, method, struct_type.name, method),
+      None => throw!(ErrorKind::Type, "No method {} found on {}", method, struct_type.name)
+    };
+  }
+
+  if let Some(body) = methods::lookup_method(&receiver, &method) {
+    if let Some((name, _)) = named.first() {
+      throw!(ErrorKind::Argument, "{} doesn't take named arguments, like {}", method, name);
+    }
+    return body(&receiver, runtime_args, &mut Interpreter::new(env));
+  }
+
+  let helper = match methods::helper_namespace(&receiver).and_then(|namespace| env.try_lookup_var(namespace)) {
+    Some(RuntimeValue::Object(helper)) => helper.borrow().properties.get(&method).cloned(),
+    _ => None
   };
-  if equals {
-    return value_if_eq;
+  match helper {
+    Some(func) => {
+      runtime_args.insert(0, receiver);
+      call_function_with(func, runtime_args, named, env)
+    },
+    None => throw!(ErrorKind::Type, "No method {} found on {:?}", method, receiver)
+  }
+}
+
+// The first arm whose pattern matches and whose guard holds is run, in a scope with
+// the names its pattern binds.
+pub fn evaluate_match_expr(subject: Expr, arms: Vec<MatchArm>, env: &mut Environment) -> Eval {
+  let value = evaluate_expr(subject, env)?;
+  let (body, mut scope) = select_arm(&value, arms, env)?;
+  evaluate_block(body, &mut scope)
+}
+
+// The body of the arm that matches, with the scope to run it in.
+pub fn select_arm(value: &RuntimeValue, arms: Vec<MatchArm>, env: &mut Environment) -> Eval<(Vec<Stmt>, Environment)> {
+  for arm in arms {
+    let mut bindings = Vec::new();
+    if !match_pattern(&arm.pattern, value, &mut bindings, env)? {
+      continue;
+    }
+    let mut scope = Environment::new(Some(env.clone()));
+    for (name, value) in bindings {
+      scope.declare_var(name, value, true)?;
+    }
+    if let Some(guard) = arm.guard {
+      if !evaluate_expr(guard, &mut scope)?.is_truthy() {
+        continue;
+      }
+    }
+    return Ok((arm.body, scope));
+  }
+  throw!(ErrorKind::Match, "No match arm matched {:?}", value)
+}
+
+pub fn evaluate_member_expr(object: Expr, property: Expr, computed: bool, env: &mut Environment) -> Eval {
+  let object = evaluate_expr(object, env)?;
+  let key = evaluate_member_key(property, computed, env)?;
+  member_of(&object, &key)
+}
+
+fn member_of(object: &RuntimeValue, key: &RuntimeValue) -> Eval {
+  Ok(match (object, key) {
+    (RuntimeValue::Object(object), RuntimeValue::String { value: key }) => {
+      object.borrow().properties.get(key).cloned().unwrap_or(RuntimeValue::Null)
+    },
+    (RuntimeValue::Array { elements }, RuntimeValue::Number { value: index }) => {
+      let elements = elements.borrow();
+      match array_index(*index, elements.len()) {
+        Some(index) => elements[index].clone(),
+        None => RuntimeValue::Null
+      }
+    },
+    (RuntimeValue::Struct(value), RuntimeValue::String { value: key }) => {
+      let value = value.borrow();
+      match value.struct_type.field_index(key) {
+        Some(index) => value.fields[index].clone(),
+        None => throw!(ErrorKind::Type, "Struct {} has no field {}", value.struct_type.name, key)
+      }
+    },
+    (RuntimeValue::EnumType(enum_type), RuntimeValue::String { value: key }) => {
+      match enum_type.variant_index(key) {
+        Some(index) if enum_type.variants[index].fields.is_empty() => RuntimeValue::enum_value(enum_type.clone(), index, Vec::new()),
+        Some(index) => RuntimeValue::Variant { enum_type: enum_type.clone(), index },
+        None => throw!(ErrorKind::Type, "Enum {} has no variant {}", enum_type.name, key)
+      }
+    },
+    (RuntimeValue::Enum(value), RuntimeValue::String { value: key }) => {
+      let value = value.borrow();
+      match value.variant().fields.iter().position(|field| field == key) {
+        Some(index) => value.values[index].clone(),
+        None => throw!(ErrorKind::Type, "{}::{} has no field {}", value.enum_type.name, value.variant().name, key)
+      }
+    },
+    (RuntimeValue::StructType(struct_type), RuntimeValue::String { value: key }) => {
+      match struct_type.methods.borrow().get(key) {
+        Some(method) => method.clone(),
+        None => throw!(ErrorKind::Type, "No method {} found on {}", key, struct_type.name)
+      }
+    },
+    (RuntimeValue::Array { elements }, RuntimeValue::Range(range)) => {
+      let elements = elements.borrow();
+      RuntimeValue::array(range.slice_indices(elements.len())?.into_iter().map(|index| elements[index].clone()).collect())
+    },
+    (RuntimeValue::String { value }, RuntimeValue::Range(range)) => {
+      let chars: Vec<char> = value.chars().collect();
+      RuntimeValue::String { value: range.slice_indices(chars.len())?.into_iter().map(|index| chars[index]).collect() }
+    },
+    (RuntimeValue::String { value }, RuntimeValue::Number { value: index }) => {
+      match array_index(*index, usize::MAX).and_then(|index| value.chars().nth(index)) {
+        Some(ch) => RuntimeValue::String { value: ch.to_string() },
+        None => RuntimeValue::Null
+      }
+    },
+    _ => throw!(ErrorKind::Type, "Cannot access {:?} of {:?}", key, object)
+  })
+}
+
+// `obj::key` names the property directly, `obj[expr]` evaluates to the key or index.
+fn evaluate_member_key(property: Expr, computed: bool, env: &mut Environment) -> Eval {
+  match (property, computed) {
+    (Expr::Ident { symbol }, false) => Ok(RuntimeValue::String { value: symbol }),
+    (property, true) => evaluate_expr(property, env),
+    (property, false) => throw!(ErrorKind::Type, "{:?} is not a valid property", property)
+  }
+}
+
+fn takes_self(func: &RuntimeValue) -> bool {
+  matches!(func, RuntimeValue::Function { params, .. } if matches!(params.first(), Some(Param { pattern: Pattern::Binding { name }, .. }) if name == "self"))
+}
+
+fn array_index(index: f32, len: usize) -> Option<usize> {
+  if index >= 0.0 && index.fract() == 0.0 && (index as usize) < len {
+    Some(index as usize)
   } else {
-    return !value_if_eq;
+    None
   }
-}
\ No newline at end of file
+}
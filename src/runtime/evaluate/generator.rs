@@ -0,0 +1,222 @@
+use std::rc::Rc;
+
+use crate::frontend::ast::{Catch, Expr, Pattern, Stmt, StmtKind};
+use crate::runtime::environment::Environment;
+use crate::runtime::errors::{locate, Eval, Interrupt};
+use crate::runtime::evaluate::expressions::{evaluate_expr, select_arm};
+use crate::runtime::evaluate::patterns::declare_pattern;
+use crate::runtime::interpreter::{evaluate_node, Interpreter};
+use crate::runtime::iteration::iterate;
+use crate::runtime::values::RuntimeValue;
+use crate::runtime::values::iterator::{IteratorValue, NativeIterator};
+
+// The body of a generator function, run one `yield` at a time. The recursive
+// evaluator keeps its state on the Rust stack, which is gone once it returns, so
+// the statements of a generator are run from an explicit stack of frames instead:
+// a block remembers the statement it is at, a loop the iterator it is going
+// through, a `try` which of its blocks is running. `yield` returns from `next`
+// with the frames left as they are, the next call carries on from there.
+// Expressions are still evaluated in one go by the evaluator, so `yield` has to be
+// a statement of the body or of the `if`, `for`, `try`, `match` and `{ }` blocks in
+// it, the latter only when the `match` or block stands as a statement itself.
+pub struct Generator {
+  frames: Vec<Frame>
+}
+
+enum Frame {
+  Block {
+    stmts: Vec<Stmt>,
+    index: usize,
+    env: Environment
+  },
+  For {
+    pattern: Pattern,
+    iterator: Rc<IteratorValue>,
+    body: Vec<Stmt>,
+    env: Environment
+  },
+  // below the frames of the block it is running
+  Try {
+    catch: Option<Catch>,
+    finally: Option<Vec<Stmt>>,
+    stage: Stage,
+    env: Environment
+  }
+}
+
+enum Stage {
+  Body,
+  Catch,
+  // holds what ended the body or catch block, to carry on with once finally is done
+  Finally(Option<Interrupt>)
+}
+
+impl Generator {
+  // `env` is the scope of the call, with the parameters declared in it.
+  pub fn new(body: Vec<Stmt>, env: Environment) -> Generator {
+    Generator { frames: vec![Frame::Block { stmts: body, index: 0, env }] }
+  }
+
+  // Runs until the next `yield` or until a frame is done, whichever comes first.
+  fn step(&mut self) -> Eval<Option<RuntimeValue>> {
+    let Some(frame) = self.frames.last_mut() else {
+      return Ok(None);
+    };
+    let (stmt, mut env) = match frame {
+      Frame::Block { stmts, index, env } => {
+        let Some(stmt) = stmts.get(*index).cloned() else {
+          self.frames.pop();
+          return Ok(None);
+        };
+        *index += 1;
+        (stmt, env.clone())
+      },
+      Frame::For { pattern, iterator, body, env } => {
+        let mut outer = env.clone();
+        match iterator.next(&mut Interpreter::new(&mut outer))? {
+          Some(value) => {
+            let mut scope = Environment::new(Some(outer));
+            declare_pattern(pattern, value, true, &mut scope)?;
+            let frame = Frame::Block { stmts: body.clone(), index: 0, env: scope };
+            self.frames.push(frame);
+          },
+          None => {
+            self.frames.pop();
+          }
+        }
+        return Ok(None);
+      },
+      Frame::Try { finally, stage, env, .. } => {
+        if let Stage::Finally(pending) = stage {
+          let pending = pending.take();
+          self.frames.pop();
+          return match pending {
+            Some(interrupt) => Err(interrupt),
+            None => Ok(None)
+          };
+        }
+        match finally.take() {
+          Some(finally) => {
+            *stage = Stage::Finally(None);
+            let scope = Environment::new(Some(env.clone()));
+            self.frames.push(Frame::Block { stmts: finally, index: 0, env: scope });
+          },
+          None => {
+            self.frames.pop();
+          }
+        }
+        return Ok(None);
+      }
+    };
+
+    let line = stmt.line;
+    let located = |interrupt| locate(interrupt, line);
+    let kind = match stmt.kind {
+      StmtKind::Expr(Expr::If { condition, then_branch, else_branch }) => StmtKind::If { condition: *condition, then_branch, else_branch },
+      kind => kind
+    };
+    match kind {
+      StmtKind::Yield { value } => {
+        return Ok(Some(match value {
+          Some(expr) => evaluate_expr(expr, &mut env).map_err(located)?,
+          None => RuntimeValue::Null
+        }));
+      },
+      StmtKind::If { condition, then_branch, else_branch } => {
+        let branch = if evaluate_expr(condition, &mut env).map_err(located)?.is_truthy() { Some(then_branch) } else { else_branch };
+        if let Some(branch) = branch {
+          let scope = Environment::new(Some(env));
+          self.frames.push(Frame::Block { stmts: branch, index: 0, env: scope });
+        }
+      },
+      StmtKind::Expr(Expr::Block { body }) => {
+        let scope = Environment::new(Some(env));
+        self.frames.push(Frame::Block { stmts: body, index: 0, env: scope });
+      },
+      StmtKind::Expr(Expr::Match { subject, arms }) => {
+        let value = evaluate_expr(*subject, &mut env).map_err(located)?;
+        let (body, scope) = select_arm(&value, arms, &mut env).map_err(located)?;
+        self.frames.push(Frame::Block { stmts: body, index: 0, env: scope });
+      },
+      StmtKind::For { pattern, iterable, body } => {
+        let iterator = iterate(&evaluate_expr(*iterable, &mut env).map_err(located)?).map_err(located)?;
+        self.frames.push(Frame::For { pattern, iterator, body, env });
+      },
+      StmtKind::Try { body, catch, finally } => {
+        let scope = Environment::new(Some(env.clone()));
+        self.frames.push(Frame::Try { catch, finally, stage: Stage::Body, env });
+        self.frames.push(Frame::Block { stmts: body, index: 0, env: scope });
+      },
+      // `ret` ends the generator through `unwind`, its value is dropped
+      kind => {
+        evaluate_node(Stmt { kind, line }, &mut env)?;
+      }
+    }
+    Ok(None)
+  }
+
+  // Pops frames up to the `try` that deals with `interrupt`. A `ret` that no
+  // finally block overrides ends the generator, a thrown value that isn't caught
+  // is thrown from `next`.
+  fn unwind(&mut self, interrupt: Interrupt) -> Eval<()> {
+    let mut interrupt = interrupt;
+    while let Some(frame) = self.frames.pop() {
+      let Frame::Try { catch, finally, stage, env } = frame else {
+        continue;
+      };
+      interrupt = match (interrupt, &stage, catch) {
+        (Interrupt::Throw(thrown), Stage::Body, Some(catch)) => {
+          let mut scope = Environment::new(Some(env.clone()));
+          if let Some(name) = catch.name {
+            scope.declare_var(name, thrown, true)?;
+          }
+          self.frames.push(Frame::Try { catch: None, finally, stage: Stage::Catch, env });
+          self.frames.push(Frame::Block { stmts: catch.body, index: 0, env: scope });
+          return Ok(());
+        },
+        (interrupt, _, _) => interrupt
+      };
+      if let (Stage::Body | Stage::Catch, Some(finally)) = (stage, finally) {
+        let scope = Environment::new(Some(env.clone()));
+        self.frames.push(Frame::Try { catch: None, finally: None, stage: Stage::Finally(Some(interrupt)), env });
+        self.frames.push(Frame::Block { stmts: finally, index: 0, env: scope });
+        return Ok(());
+      }
+    }
+    match interrupt {
+      Interrupt::Return(_) => Ok(()),
+      interrupt => Err(interrupt)
+    }
+  }
+}
+
+impl NativeIterator for Generator {
+  fn next(&mut self, _: &mut Interpreter) -> Eval<Option<RuntimeValue>> {
+    while !self.frames.is_empty() {
+      match self.step() {
+        Ok(Some(value)) => return Ok(Some(value)),
+        Ok(None) => (),
+        Err(interrupt) => self.unwind(interrupt)?
+      }
+    }
+    Ok(None)
+  }
+
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+    for frame in self.frames.iter() {
+      match frame {
+        Frame::Block { env, .. } => visit(env.heap_ptr()),
+        Frame::For { iterator, env, .. } => {
+          visit(Rc::as_ptr(iterator) as *const ());
+          visit(env.heap_ptr());
+        },
+        Frame::Try { stage, env, .. } => {
+          if let Stage::Finally(Some(Interrupt::Return(value) | Interrupt::Throw(value))) = stage {
+            value.trace(visit);
+          }
+          visit(env.heap_ptr());
+        }
+      }
+    }
+  }
+}
@@ -1,2 +1,4 @@
 pub mod expressions;
-pub mod statements;
\ No newline at end of file
+pub mod statements;
+pub mod patterns;
+pub mod generator;
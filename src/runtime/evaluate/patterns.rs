@@ -0,0 +1,122 @@
+use std::rc::Rc;
+
+use crate::frontend::ast::Pattern;
+use crate::runtime::environment::Environment;
+use crate::runtime::errors::{throw, ErrorKind, Eval};
+use crate::runtime::values::{EnumType, RuntimeValue};
+use crate::runtime::values::compare::equals;
+use crate::runtime::evaluate::expressions::evaluate_expr;
+
+// Tests `value` against `pattern`, pushing the names the pattern binds onto
+// `bindings`. When it doesn't match, `bindings` may hold part of the names.
+pub fn match_pattern(pattern: &Pattern, value: &RuntimeValue, bindings: &mut Vec<(String, RuntimeValue)>, env: &mut Environment) -> Eval<bool> {
+  match pattern {
+    Pattern::Wildcard => Ok(true),
+    Pattern::Binding { name } => {
+      if bindings.iter().any(|(bound, _)| bound == name) {
+        throw!(ErrorKind::Match, "{} is bound more than once in the same pattern", name);
+      }
+      bindings.push((name.clone(), value.clone()));
+      Ok(true)
+    },
+    Pattern::Literal { value: literal } => Ok(equals(&evaluate_expr(literal.clone(), env)?, value)),
+    Pattern::Variant { enum_name: Some(enum_name), variant, fields } => {
+      let enum_type = match env.lookup_var(enum_name.clone())? {
+        RuntimeValue::EnumType(enum_type) => enum_type,
+        other => throw!(ErrorKind::Type, "{} is a {}, not an enum", enum_name, other.type_name())
+      };
+      let Some(index) = enum_type.variant_index(variant) else {
+        throw!(ErrorKind::Match, "Enum {} has no variant {}", enum_name, variant);
+      };
+      match_variant(pattern, enum_type, index, fields, value, bindings, env)
+    },
+    Pattern::Variant { enum_name: None, variant, fields } => {
+      let (enum_type, index) = match env.lookup_var(variant.clone())? {
+        RuntimeValue::Variant { enum_type, index } => (enum_type, index),
+        other => throw!(ErrorKind::Type, "{} is a {}, not a variant", variant, other.type_name())
+      };
+      match_variant(pattern, enum_type, index, fields, value, bindings, env)
+    },
+    Pattern::Array { elements: patterns } => {
+      let RuntimeValue::Array { elements } = value else {
+        return Ok(false);
+      };
+      let elements = elements.borrow().clone();
+      let Some(rest) = patterns.iter().position(|pattern| matches!(pattern, Pattern::Rest { .. })) else {
+        return Ok(elements.len() == patterns.len() && match_all(patterns, &elements, bindings, env)?);
+      };
+      // the patterns after the rest match the last elements
+      let after = patterns.len() - rest - 1;
+      if elements.len() < rest + after {
+        return Ok(false);
+      }
+      let rest_end = elements.len() - after;
+      Ok(match_all(&patterns[..rest], &elements[..rest], bindings, env)?
+        && match_pattern(&patterns[rest], &RuntimeValue::array(elements[rest..rest_end].to_vec()), bindings, env)?
+        && match_all(&patterns[rest + 1..], &elements[rest_end..], bindings, env)?)
+    },
+    Pattern::Rest { name: Some(name) } => match_pattern(&Pattern::Binding { name: name.clone() }, value, bindings, env),
+    Pattern::Rest { name: None } => Ok(true),
+    Pattern::Object { properties } => {
+      if !matches!(value, RuntimeValue::Object(_) | RuntimeValue::Struct(_)) {
+        return Ok(false);
+      }
+      for (key, pattern) in properties {
+        let property = match value {
+          RuntimeValue::Object(object) => object.borrow().properties.get(key).cloned(),
+          RuntimeValue::Struct(value) => {
+            let value = value.borrow();
+            value.struct_type.field_index(key).map(|index| value.fields[index].clone())
+          },
+          _ => return Ok(false)
+        };
+        match property {
+          Some(property) if match_pattern(pattern, &property, bindings, env)? => (),
+          _ => return Ok(false)
+        }
+      }
+      Ok(true)
+    }
+  }
+}
+
+fn match_variant(
+  pattern: &Pattern, enum_type: Rc<EnumType>, index: usize, fields: &[Pattern],
+  value: &RuntimeValue, bindings: &mut Vec<(String, RuntimeValue)>, env: &mut Environment
+) -> Eval<bool> {
+  let expected = enum_type.variants[index].fields.len();
+  if fields.len() != expected {
+    throw!(ErrorKind::Match, "Pattern {} has {} fields but the variant has {}", pattern, fields.len(), expected);
+  }
+  let RuntimeValue::Enum(value) = value else {
+    return Ok(false);
+  };
+  let value = value.borrow().clone();
+  if !Rc::ptr_eq(&value.enum_type, &enum_type) || value.variant != index {
+    return Ok(false);
+  }
+  match_all(fields, &value.values, bindings, env)
+}
+
+// Matches the patterns against the values pairwise, stopping at the first that doesn't fit.
+fn match_all(patterns: &[Pattern], values: &[RuntimeValue], bindings: &mut Vec<(String, RuntimeValue)>, env: &mut Environment) -> Eval<bool> {
+  for (pattern, value) in patterns.iter().zip(values.iter()) {
+    if !match_pattern(pattern, value, bindings, env)? {
+      return Ok(false);
+    }
+  }
+  Ok(true)
+}
+
+// Declares the names `pattern` binds in `env`, for `let`, `for` and parameters,
+// where a value that doesn't fit the pattern is an error.
+pub fn declare_pattern(pattern: &Pattern, value: RuntimeValue, mutable: bool, env: &mut Environment) -> Eval<()> {
+  let mut bindings = Vec::new();
+  if !match_pattern(pattern, &value, &mut bindings, env)? {
+    throw!(ErrorKind::Match, "Cannot destructure {:?} with the pattern {}", value, pattern);
+  }
+  for (name, value) in bindings {
+    env.declare_var(name, value, mutable)?;
+  }
+  Ok(())
+}
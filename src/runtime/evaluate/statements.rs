@@ -1,17 +1,22 @@
-use crate::frontend::ast::{Expr, Stmt};
+use crate::frontend::ast::{Catch, Expr, Param, Pattern, Stmt, StmtKind, Variant};
 use crate::runtime::environment::Environment;
+use crate::runtime::errors::{throw, ErrorKind, Eval, Interrupt};
 use crate::runtime::values::RuntimeValue;
 use crate::runtime::evaluate::expressions::evaluate_expr;
+use crate::runtime::evaluate::patterns::declare_pattern;
+use crate::runtime::interpreter::{evaluate_node, Interpreter};
+use crate::runtime::iteration::iterate;
 
-pub fn evaluate_var_decl(mutable: bool, name: String, value: Option<Expr>, env: &mut Environment) -> RuntimeValue {
+pub fn evaluate_var_decl(mutable: bool, pattern: Pattern, value: Option<Expr>, env: &mut Environment) -> Eval {
   let res = match value {
-    Some(expr) => evaluate_expr(expr, env),
+    Some(expr) => evaluate_expr(expr, env)?,
     None => RuntimeValue::Null
   };
-  env.declare_var(name, res, mutable)
+  declare_pattern(&pattern, res.clone(), mutable, env)?;
+  Ok(res)
 }
 
-pub fn evaluate_func_decl(params: Vec<String>, name: String, body: Vec<Stmt>, env: &mut Environment) -> RuntimeValue {
+pub fn evaluate_func_decl(params: Vec<Param>, name: String, body: Vec<Stmt>, env: &mut Environment) -> Eval {
   let func = RuntimeValue::Function {
     name: name.clone(),
     params: params.clone(),
@@ -21,62 +26,115 @@ pub fn evaluate_func_decl(params: Vec<String>, name: String, body: Vec<Stmt>, en
   env.declare_var(name, func, true)
 }
 
-pub fn evaluate_if_stmt(condition: Expr, then_branch: Vec<Stmt>, else_branch: Option<Vec<Stmt>>, env: &mut Environment) -> RuntimeValue {
-  let condition = evaluate_expr(condition, env);
-  match condition {
-    RuntimeValue::Bool { value } => {
-      if value == true {
-        evaluate_branch(then_branch, env)
-      } else {
-        match else_branch {
-          Some(branch) => evaluate_branch(branch, env),
-          None => RuntimeValue::Null
-        }
-      }
-    },
-    RuntimeValue::Null => {
-      match else_branch {
-        Some(branch) => evaluate_branch(branch, env),
-        None => RuntimeValue::Null
+pub fn evaluate_struct_decl(name: String, fields: Vec<String>, env: &mut Environment) -> Eval {
+  for (i, field) in fields.iter().enumerate() {
+    if fields[..i].contains(field) {
+      throw!(ErrorKind::Type, "Field {} is declared twice in struct {}", field, name);
+    }
+  }
+  let struct_type = RuntimeValue::struct_type(name.clone(), fields);
+  env.declare_var(name, struct_type, false)
+}
+
+// Methods that take `self` first are called on values, `p.len()`, the others through
+// the type, `Point::new(1, 2)`.
+pub fn evaluate_impl(name: String, methods: Vec<Stmt>, env: &mut Environment) -> Eval {
+  let struct_type = match env.lookup_var(name.clone())? {
+    RuntimeValue::StructType(struct_type) => struct_type,
+    other => throw!(ErrorKind::Type, "Cannot impl {} because it is a {}, not a struct", name, other.type_name())
+  };
+  for method in methods {
+    let StmtKind::FuncDecl { params, name: method, body } = method.kind else {
+      throw!(ErrorKind::Type, "Only functions can be declared in an impl block");
+    };
+    if struct_type.field_index(&method).is_some() || struct_type.methods.borrow().contains_key(&method) {
+      throw!(ErrorKind::Reference, "{} already has a field or method named {}", name, method);
+    }
+    let func = RuntimeValue::Function {
+      name: method.clone(),
+      params,
+      decl_env: env.clone(),
+      body
+    };
+    struct_type.methods.borrow_mut().insert(method, func);
+  }
+  Ok(RuntimeValue::Null)
+}
+
+pub fn evaluate_enum_decl(name: String, variants: Vec<Variant>, env: &mut Environment) -> Eval {
+  for (i, variant) in variants.iter().enumerate() {
+    if variants[..i].iter().any(|other| other.name == variant.name) {
+      throw!(ErrorKind::Type, "Variant {} is declared twice in enum {}", variant.name, name);
+    }
+  }
+  let enum_type = RuntimeValue::enum_type(name.clone(), variants);
+  env.declare_var(name, enum_type, false)
+}
+
+pub fn evaluate_if_stmt(condition: Expr, then_branch: Vec<Stmt>, else_branch: Option<Vec<Stmt>>, env: &mut Environment) -> Eval {
+  let condition = evaluate_expr(condition, env)?;
+  if condition.is_truthy() {
+    evaluate_branch(then_branch, env)
+  } else {
+    match else_branch {
+      Some(branch) => evaluate_branch(branch, env),
+      None => Ok(RuntimeValue::Null)
+    }
+  }
+}
+
+pub fn evaluate_branch(branch: Vec<Stmt>, env: &mut Environment) -> Eval {
+  let mut scope = Environment::new(Some(env.clone()));
+  evaluate_block(branch, &mut scope)
+}
+
+// Runs the statements in `env` itself, the caller decides which scope they belong to.
+pub fn evaluate_block(block: Vec<Stmt>, env: &mut Environment) -> Eval {
+  let mut res: RuntimeValue = RuntimeValue::Null;
+  for stmt in block {
+    res = match stmt.kind {
+      StmtKind::Expr(_) | StmtKind::If { .. } | StmtKind::For { .. } | StmtKind::Try { .. } => evaluate_node(stmt, env)?,
+      _ => {
+        evaluate_node(stmt, env)?;
+        RuntimeValue::Null
       }
-    },
-    _ => evaluate_branch(then_branch, env)
+    };
   }
+  Ok(res)
 }
 
-pub fn evaluate_branch(branch: Vec<Stmt>, env: &mut Environment) -> RuntimeValue {
+pub fn evaluate_for_stmt(pattern: Pattern, iterable: Expr, body: Vec<Stmt>, env: &mut Environment) -> Eval {
+  let iterable = evaluate_expr(iterable, env)?;
+  let iterator = iterate(&iterable)?;
   let mut res: RuntimeValue = RuntimeValue::Null;
-  for stmt in branch {
-    match stmt {
-      Stmt::Return { value } => {
-        res = match value {
-          Some(expr) => evaluate_expr(expr, env),
-          None => RuntimeValue::Null
-        };
-        break;
-      },
-      Stmt::Expr(expr) => res = evaluate_expr(expr, env),
-      Stmt::If { condition, then_branch, else_branch } => res = evaluate_if_stmt(condition, then_branch, else_branch, env),
-      Stmt::For { ident, iterable, body } => res = evaluate_for_stmt(ident, *iterable, body, env),
-      _ => res = RuntimeValue::Null
-    }
+  while let Some(value) = iterator.next(&mut Interpreter::new(env))? {
+    let mut scope = Environment::new(Some(env.clone()));
+    declare_pattern(&pattern, value, true, &mut scope)?;
+    res = evaluate_block(body.clone(), &mut scope)?;
   }
-  res
+  Ok(res)
+}
+
+pub fn evaluate_throw(value: Expr, env: &mut Environment) -> Eval {
+  Err(Interrupt::Throw(evaluate_expr(value, env)?))
 }
 
-pub fn evaluate_for_stmt(ident: String, iterable: Expr, body: Vec<Stmt>, env: &mut Environment) -> RuntimeValue {
-  let iterable = evaluate_expr(iterable, env);
-  match iterable {
-    RuntimeValue::Array { elements } => {
-      let mut res: RuntimeValue = RuntimeValue::Null;
-      env.declare_var(ident.clone(), elements.first().unwrap().clone(), true);
-      for element in elements {
-        env.assign_var(ident.clone(), &element);
-        res = evaluate_branch(body.clone(), env);
+// A thrown value is caught by the catch block, a `ret` passes through. The finally
+// block runs however the try ends, and when it throws or returns itself that takes
+// the place of how the try ended.
+pub fn evaluate_try(body: Vec<Stmt>, catch: Option<Catch>, finally: Option<Vec<Stmt>>, env: &mut Environment) -> Eval {
+  let res = match (evaluate_branch(body, env), catch) {
+    (Err(Interrupt::Throw(thrown)), Some(catch)) => {
+      let mut scope = Environment::new(Some(env.clone()));
+      match catch.name {
+        Some(name) => scope.declare_var(name, thrown, true).and_then(|_| evaluate_block(catch.body, &mut scope)),
+        None => evaluate_block(catch.body, &mut scope)
       }
-      env.void_var(ident);
-      res
     },
-    _ => panic!("You can only iterate over arrays")
+    (res, _) => res
+  };
+  if let Some(finally) = finally {
+    evaluate_branch(finally, env)?;
   }
-}
\ No newline at end of file
+  res
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use crate::runtime::values::RuntimeValue;
+
+// The arguments as `format!` receives them. When the template has named
+// placeholders, the last argument is the object holding the named arguments.
+pub fn format_args(template: &str, args: &[RuntimeValue]) -> Result<String, String> {
+  if !has_named_placeholder(template) {
+    return format(template, args, &HashMap::new());
+  }
+  match args.split_last() {
+    Some((RuntimeValue::Object(named), positional)) => format(template, positional, &named.borrow().properties),
+    _ => Err("Format string uses named arguments, pass them as an object after the other arguments".to_string())
+  }
+}
+
+fn has_named_placeholder(template: &str) -> bool {
+  let mut chars = template.chars().peekable();
+  while let Some(ch) = chars.next() {
+    if ch != '{' {
+      continue;
+    }
+    match chars.peek() {
+      Some('{') => {
+        chars.next();
+      },
+      Some(ch) if ch.is_alphabetic() || *ch == '_' => return true,
+      _ => ()
+    }
+  }
+  false
+}
+
+// Formats `template` the way `format!` does. Placeholders are `{}` for the next
+// argument, `{1}` for an argument by position or `{name}` for a named argument,
+// optionally followed by a spec: `{:[[fill]align][+][#][0][width][.precision][?]}`.
+// `{{` and `}}` stand for literal braces.
+pub fn format(template: &str, positional: &[RuntimeValue], named: &HashMap<String, RuntimeValue>) -> Result<String, String> {
+  let mut out = String::new();
+  let mut used = vec![false; positional.len()];
+  let mut next = 0;
+  let mut chars = template.chars().peekable();
+
+  while let Some(ch) = chars.next() {
+    match ch {
+      '{' if chars.peek() == Some(&'{') => {
+        chars.next();
+        out.push('{');
+      },
+      '}' if chars.peek() == Some(&'}') => {
+        chars.next();
+        out.push('}');
+      },
+      '}' => return Err("Unmatched '}' in format string, use '}}' for a literal brace".to_string()),
+      '{' => {
+        let mut placeholder = String::new();
+        loop {
+          match chars.next() {
+            Some('}') => break,
+            Some(ch) => placeholder.push(ch),
+            None => return Err("Unterminated placeholder in format string, use '{{' for a literal brace".to_string())
+          }
+        }
+        let (arg, spec) = match placeholder.split_once(':') {
+          Some((arg, spec)) => (arg, spec),
+          None => (placeholder.as_str(), "")
+        };
+        let spec = parse_spec(spec)?;
+
+        let value = if arg.is_empty() || arg.chars().all(|ch| ch.is_ascii_digit()) {
+          let shown = if arg.is_empty() { next.to_string() } else { arg.to_string() };
+          // an index too large for a usize can't be one of the arguments either
+          let index = if arg.is_empty() {
+            next += 1;
+            Some(next - 1)
+          } else {
+            arg.parse::<usize>().ok()
+          };
+          match index.and_then(|index| positional.get(index).map(|value| (index, value))) {
+            Some((index, value)) => {
+              used[index] = true;
+              value
+            },
+            None => return Err(format!(
+              "Format string refers to argument {} but {} positional argument{} given",
+              shown, positional.len(), if positional.len() == 1 { " was" } else { "s were" }
+            ))
+          }
+        } else {
+          match named.get(arg) {
+            Some(value) => value,
+            None => return Err(format!("Format string refers to named argument `{}` which was not given", arg))
+          }
+        };
+        out.push_str(&apply_spec(value, &spec));
+      },
+      ch => out.push(ch)
+    }
+  }
+
+  if let Some(unused) = used.iter().position(|used| !used) {
+    return Err(format!("Argument {} is never used by the format string", unused));
+  }
+  Ok(out)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Align {
+  Left,
+  Center,
+  Right
+}
+
+struct Spec {
+  fill: char,
+  align: Option<Align>,
+  plus: bool,
+  alternate: bool,
+  zero: bool,
+  width: usize,
+  precision: Option<usize>,
+  debug: bool
+}
+
+fn parse_spec(spec: &str) -> Result<Spec, String> {
+  let mut parsed = Spec { fill: ' ', align: None, plus: false, alternate: false, zero: false, width: 0, precision: None, debug: false };
+  let chars: Vec<char> = spec.chars().collect();
+  let mut i = 0;
+
+  let align = |ch: Option<&char>| match ch {
+    Some('<') => Some(Align::Left),
+    Some('^') => Some(Align::Center),
+    Some('>') => Some(Align::Right),
+    _ => None
+  };
+  if let Some(align) = align(chars.get(1)) {
+    parsed.fill = chars[0];
+    parsed.align = Some(align);
+    i = 2;
+  } else if let Some(align) = align(chars.first()) {
+    parsed.align = Some(align);
+    i = 1;
+  }
+  if chars.get(i) == Some(&'+') {
+    parsed.plus = true;
+    i += 1;
+  }
+  if chars.get(i) == Some(&'#') {
+    parsed.alternate = true;
+    i += 1;
+  }
+  if chars.get(i) == Some(&'0') {
+    parsed.zero = true;
+    i += 1;
+  }
+  let start = i;
+  while chars.get(i).is_some_and(|ch| ch.is_ascii_digit()) {
+    i += 1;
+  }
+  if i > start {
+    parsed.width = chars[start..i].iter().collect::<String>().parse().map_err(|_| format!("Invalid width in format spec ':{}'", spec))?;
+  }
+  if chars.get(i) == Some(&'.') {
+    i += 1;
+    let start = i;
+    while chars.get(i).is_some_and(|ch| ch.is_ascii_digit()) {
+      i += 1;
+    }
+    if i == start {
+      return Err(format!("Missing precision after '.' in format spec ':{}'", spec));
+    }
+    parsed.precision = Some(chars[start..i].iter().collect::<String>().parse().map_err(|_| format!("Invalid precision in format spec ':{}'", spec))?);
+  }
+  if chars.get(i) == Some(&'?') {
+    parsed.debug = true;
+    i += 1;
+  }
+  if i != chars.len() {
+    return Err(format!("Invalid format spec ':{}'", spec));
+  }
+  Ok(parsed)
+}
+
+fn apply_spec(value: &RuntimeValue, spec: &Spec) -> String {
+  let (sign, mut text) = match value {
+    RuntimeValue::Number { value: number } if number.is_finite() => {
+      let sign = if number.is_sign_negative() && *number != 0.0 {
+        "-"
+      } else if spec.plus {
+        "+"
+      } else {
+        ""
+      };
+      let digits = match spec.precision {
+        Some(precision) => format!("{:.*}", precision, number.abs()),
+        None => RuntimeValue::Number { value: number.abs() }.to_string()
+      };
+      (sign, digits)
+    },
+    value => {
+      let mut text = match (spec.debug, spec.alternate) {
+        (true, true) => format!("{:#?}", value),
+        (true, false) => format!("{:?}", value),
+        (false, true) => format!("{:#}", value),
+        (false, false) => value.to_string()
+      };
+      if let (Some(precision), RuntimeValue::String { .. }, false) = (spec.precision, value, spec.debug) {
+        text = text.chars().take(precision).collect();
+      }
+      ("", text)
+    }
+  };
+
+  let len = sign.chars().count() + text.chars().count();
+  if len >= spec.width {
+    return format!("{}{}", sign, text);
+  }
+  let padding = spec.width - len;
+  if spec.zero && matches!(value, RuntimeValue::Number { .. }) {
+    text.insert_str(0, &"0".repeat(padding));
+    return format!("{}{}", sign, text);
+  }
+
+  let default_align = match value {
+    RuntimeValue::Number { .. } => Align::Right,
+    _ => Align::Left
+  };
+  let (before, after) = match spec.align.unwrap_or(default_align) {
+    Align::Left => (0, padding),
+    Align::Right => (padding, 0),
+    Align::Center => (padding / 2, padding - padding / 2)
+  };
+  let fill = |count: usize| spec.fill.to_string().repeat(count);
+  format!("{}{}{}{}", fill(before), sign, text, fill(after))
+}
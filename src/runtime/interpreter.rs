@@ -1,26 +1,78 @@
 use crate::runtime::values::RuntimeValue;
 use crate::runtime::environment;
-use crate::runtime::evaluate::expressions::evaluate_expr;
-use crate::runtime::evaluate::statements::{evaluate_var_decl, evaluate_func_decl, evaluate_if_stmt, evaluate_for_stmt};
-use crate::frontend::ast::{Program, Stmt};
+use crate::runtime::modules::evaluate_import;
+use crate::runtime::errors::{error, locate, ErrorKind, Eval, Interrupt};
+use crate::runtime::evaluate::expressions::{call_function, evaluate_expr};
+use crate::runtime::evaluate::statements::{evaluate_var_decl, evaluate_func_decl, evaluate_if_stmt, evaluate_for_stmt, evaluate_struct_decl, evaluate_impl, evaluate_enum_decl, evaluate_throw, evaluate_try};
+use crate::frontend::ast::{Expr, Program, Stmt, StmtKind};
 
 
-pub fn evaluate(prog: Program, env: &mut environment::Environment) -> RuntimeValue {
+// The handle natives, built-in methods and native iterators are called with. Through
+// it they can call the functions they are given, an error those throw comes back as
+// `Err` to pass on with `?`.
+pub struct Interpreter<'a> {
+  env: &'a mut environment::Environment
+}
+
+impl<'a> Interpreter<'a> {
+  pub fn new(env: &'a mut environment::Environment) -> Interpreter<'a> {
+    Interpreter { env }
+  }
+
+  // Calls a function, a native or an enum variant with the given arguments.
+  pub fn call(&mut self, callee: &RuntimeValue, args: Vec<RuntimeValue>) -> Eval {
+    call_function(callee.clone(), args, self.env)
+  }
+
+  // The environment the native was called from.
+  pub fn env(&mut self) -> &mut environment::Environment {
+    self.env
+  }
+}
+
+// An error no `try` caught ends the program, it comes back as `Interrupt::Throw`.
+pub fn evaluate(prog: Program, env: &mut environment::Environment) -> Eval {
   let mut last = RuntimeValue::Null;
   for stmt in prog.body {
-    last = evaluate_node(stmt, env);
+    let line = stmt.line;
+    last = match evaluate_node(stmt, env) {
+      Err(Interrupt::Return(_)) => {
+        return Err(locate(error(ErrorKind::Type, "You can only return from inside a function.".to_string()), line));
+      },
+      res => res?
+    };
   }
-  last
+  Ok(last)
 }
 
-pub fn evaluate_node(node: Stmt, env: &mut environment::Environment) -> RuntimeValue {
-  match node {
-    Stmt::Expr(node) => evaluate_expr(node, env),
-    Stmt::VarDecl { mutable, name, value } => evaluate_var_decl(mutable, name, value, env),
-    Stmt::FuncDecl { params, name, body } => evaluate_func_decl(params, name, body, env),
-    Stmt::Return { value: _ } => panic!("You can only return from inside a function."),
-    Stmt::If { condition, then_branch, else_branch } => evaluate_if_stmt(condition, then_branch, else_branch, env),
-    Stmt::For { ident, iterable, body } => evaluate_for_stmt(ident, *iterable, body, env),
-    _ => panic!("Not implemented {:?}", node)
-  }
-}
\ No newline at end of file
+// `ret` comes back as `Interrupt::Return`, to unwind to the function call.
+pub fn evaluate_node(node: Stmt, env: &mut environment::Environment) -> Eval {
+  let line = node.line;
+  let res = match node.kind {
+    StmtKind::Expr(node) => evaluate_expr(node, env),
+    StmtKind::VarDecl { mutable, pattern, value } => evaluate_var_decl(mutable, pattern, value, env),
+    StmtKind::FuncDecl { params, name, body } => evaluate_func_decl(params, name, body, env),
+    StmtKind::Return { value } => evaluate_return(value, env),
+    StmtKind::Yield { value: _ } => {
+      Err(error(ErrorKind::Type, "You can only yield from a function body, or from the if, for, try and match blocks in it.".to_string()))
+    },
+    StmtKind::If { condition, then_branch, else_branch } => evaluate_if_stmt(condition, then_branch, else_branch, env),
+    StmtKind::For { pattern, iterable, body } => evaluate_for_stmt(pattern, *iterable, body, env),
+    StmtKind::StructDecl { name, fields } => evaluate_struct_decl(name, fields, env),
+    StmtKind::Impl { name, methods } => evaluate_impl(name, methods, env),
+    StmtKind::EnumDecl { name, variants } => evaluate_enum_decl(name, variants, env),
+    StmtKind::Throw { value } => evaluate_throw(value, env),
+    StmtKind::Try { body, catch, finally } => evaluate_try(body, catch, finally, env),
+    StmtKind::Export { decl } => evaluate_node(*decl, env),
+    StmtKind::Import { path, bindings } => evaluate_import(path, bindings, env),
+  };
+  res.map_err(|interrupt| locate(interrupt, line))
+}
+
+fn evaluate_return(value: Option<Expr>, env: &mut environment::Environment) -> Eval {
+  let value = match value {
+    Some(expr) => evaluate_expr(expr, env)?,
+    None => RuntimeValue::Null
+  };
+  Err(Interrupt::Return(value))
+}
@@ -0,0 +1,171 @@
+use std::rc::Rc;
+
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::errors::{throw, ErrorKind, Eval};
+use crate::runtime::values::RuntimeValue;
+use crate::runtime::values::iterator::{IteratorValue, NativeIterator};
+
+// The iteration protocol used by `for` loops, `iter!` and the lazy iterator
+// methods. Arrays iterate over their elements and strings over their characters.
+// Objects iterate over `[key, value]` entries ordered by key, unless they have a
+// `next` function, then they are user iterators: `next` is called for every value
+// until it returns `done`. Struct values are user iterators when their struct has
+// a `next(self)` method. An iterator is returned as it is, so a loop continues
+// where the iterator was left.
+pub fn iterate(value: &RuntimeValue) -> Eval<Rc<IteratorValue>> {
+  Ok(match value {
+    RuntimeValue::Iterator(iterator) => iterator.clone(),
+    // a snapshot, so a loop body may modify the array it loops over
+    RuntimeValue::Array { elements } => IteratorValue::new(Values::new(elements.borrow().clone())),
+    RuntimeValue::String { value } => {
+      IteratorValue::new(Values::new(value.chars().map(|ch| RuntimeValue::String { value: ch.to_string() }).collect()))
+    },
+    RuntimeValue::Range(range) => {
+      let numbers = range.iter()?.map(|value| RuntimeValue::Number { value });
+      IteratorValue::new(Numbers { numbers: Box::new(numbers) })
+    },
+    RuntimeValue::Object(object) => {
+      let next = object.borrow().properties.get("next").cloned();
+      match next {
+        Some(next @ (RuntimeValue::Function { .. } | RuntimeValue::NativeFunction(_))) => {
+          IteratorValue::new(User { receiver: value.clone(), next, pass_self: false })
+        },
+        _ => {
+          let object = object.borrow();
+          let mut keys: Vec<&String> = object.properties.keys().collect();
+          keys.sort();
+          let entries = keys.into_iter().map(|key| {
+            RuntimeValue::array(vec![RuntimeValue::String { value: key.clone() }, object.properties[key].clone()])
+          }).collect();
+          IteratorValue::new(Values::new(entries))
+        }
+      }
+    },
+    RuntimeValue::Struct(struct_value) => {
+      let struct_type = struct_value.borrow().struct_type.clone();
+      let next = struct_type.methods.borrow().get("next").cloned();
+      match next {
+        Some(next) => IteratorValue::new(User { receiver: value.clone(), next, pass_self: true }),
+        None => throw!(ErrorKind::Type, "{} is not iterable, it has no next method", struct_type.name)
+      }
+    },
+    _ => throw!(ErrorKind::Type, "{} is not iterable", value.type_name())
+  })
+}
+
+struct Values {
+  values: std::vec::IntoIter<RuntimeValue>
+}
+
+impl Values {
+  fn new(values: Vec<RuntimeValue>) -> Values {
+    Values { values: values.into_iter() }
+  }
+}
+
+impl NativeIterator for Values {
+  fn next(&mut self, _: &mut Interpreter) -> Eval<Option<RuntimeValue>> {
+    Ok(self.values.next())
+  }
+
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+    for value in self.values.as_slice() {
+      value.trace(visit);
+    }
+  }
+}
+
+struct Numbers {
+  numbers: Box<dyn Iterator<Item = RuntimeValue>>
+}
+
+impl NativeIterator for Numbers {
+  fn next(&mut self, _: &mut Interpreter) -> Eval<Option<RuntimeValue>> {
+    Ok(self.numbers.next())
+  }
+
+  fn trace(&self, _: &mut dyn FnMut(*const ())) {}
+}
+
+struct User {
+  receiver: RuntimeValue,
+  next: RuntimeValue,
+  pass_self: bool
+}
+
+impl NativeIterator for User {
+  fn next(&mut self, interpreter: &mut Interpreter) -> Eval<Option<RuntimeValue>> {
+    let args = if self.pass_self { vec![self.receiver.clone()] } else { Vec::new() };
+    Ok(match interpreter.call(&self.next, args)? {
+      RuntimeValue::Done => None,
+      value => Some(value)
+    })
+  }
+
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+    self.receiver.trace(visit);
+    self.next.trace(visit);
+  }
+}
+
+// `iterator.map(f)`, calls `f` for a value only when that value is asked for.
+pub struct Map {
+  pub source: Rc<IteratorValue>,
+  pub func: RuntimeValue
+}
+
+impl NativeIterator for Map {
+  fn next(&mut self, interpreter: &mut Interpreter) -> Eval<Option<RuntimeValue>> {
+    let Some(value) = self.source.next(interpreter)? else {
+      return Ok(None);
+    };
+    Ok(Some(interpreter.call(&self.func, vec![value])?))
+  }
+
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+    visit(Rc::as_ptr(&self.source) as *const ());
+    self.func.trace(visit);
+  }
+}
+
+// `iterator.filter(f)`, skips the values for which `f` isn't truthy.
+pub struct Filter {
+  pub source: Rc<IteratorValue>,
+  pub func: RuntimeValue
+}
+
+impl NativeIterator for Filter {
+  fn next(&mut self, interpreter: &mut Interpreter) -> Eval<Option<RuntimeValue>> {
+    while let Some(value) = self.source.next(interpreter)? {
+      if interpreter.call(&self.func, vec![value.clone()])?.is_truthy() {
+        return Ok(Some(value));
+      }
+    }
+    Ok(None)
+  }
+
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+    visit(Rc::as_ptr(&self.source) as *const ());
+    self.func.trace(visit);
+  }
+}
+
+// `iterator.take(n)`, stops after `n` values, which makes endless iterators finite.
+pub struct Take {
+  pub source: Rc<IteratorValue>,
+  pub remaining: usize
+}
+
+impl NativeIterator for Take {
+  fn next(&mut self, interpreter: &mut Interpreter) -> Eval<Option<RuntimeValue>> {
+    if self.remaining == 0 {
+      return Ok(None);
+    }
+    self.remaining -= 1;
+    self.source.next(interpreter)
+  }
+
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+    visit(Rc::as_ptr(&self.source) as *const ());
+  }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use crate::runtime::errors::{throw, ErrorKind, Eval};
+use crate::runtime::values::{Arity, NativeBody, RuntimeValue};
+
+// The `Math` object. Functions of a single number work on any number, with NaN as
+// the result where there is none, like `Math::sqrt(0 - 1)`. Arguments that aren't
+// numbers are an error.
+pub fn math_object() -> RuntimeValue {
+  let mut map = HashMap::new();
+  for (name, value) in [
+    ("PI", std::f32::consts::PI),
+    ("E", std::f32::consts::E),
+    ("INF", f32::INFINITY),
+    ("NAN", f32::NAN)
+  ] {
+    map.insert(name.to_string(), RuntimeValue::Number { value });
+  }
+
+  let functions: [(&str, Arity, NativeBody); 24] = [
+    ("abs", Arity::Exact(1), |args, _| unary("Math::abs", &args, f32::abs)),
+    ("sqrt", Arity::Exact(1), |args, _| unary("Math::sqrt", &args, f32::sqrt)),
+    ("exp", Arity::Exact(1), |args, _| unary("Math::exp", &args, f32::exp)),
+    ("ln", Arity::Exact(1), |args, _| unary("Math::ln", &args, f32::ln)),
+    ("log10", Arity::Exact(1), |args, _| unary("Math::log10", &args, f32::log10)),
+    ("floor", Arity::Exact(1), |args, _| unary("Math::floor", &args, f32::floor)),
+    ("ceil", Arity::Exact(1), |args, _| unary("Math::ceil", &args, f32::ceil)),
+    ("round", Arity::Exact(1), |args, _| unary("Math::round", &args, f32::round)),
+    ("trunc", Arity::Exact(1), |args, _| unary("Math::trunc", &args, f32::trunc)),
+    ("sin", Arity::Exact(1), |args, _| unary("Math::sin", &args, f32::sin)),
+    ("cos", Arity::Exact(1), |args, _| unary("Math::cos", &args, f32::cos)),
+    ("tan", Arity::Exact(1), |args, _| unary("Math::tan", &args, f32::tan)),
+    ("asin", Arity::Exact(1), |args, _| unary("Math::asin", &args, f32::asin)),
+    ("acos", Arity::Exact(1), |args, _| unary("Math::acos", &args, f32::acos)),
+    ("atan", Arity::Exact(1), |args, _| unary("Math::atan", &args, f32::atan)),
+    // 0 for 0, where `signum` would give 1
+    ("sign", Arity::Exact(1), |args, _| unary("Math::sign", &args, |x| if x == 0.0 { 0.0 } else { x.signum() })),
+    ("pow", Arity::Exact(2), |args, _| binary("Math::pow", &args, f32::powf)),
+    ("atan2", Arity::Exact(2), |args, _| binary("Math::atan2", &args, f32::atan2)),
+    ("hypot", Arity::Exact(2), |args, _| binary("Math::hypot", &args, f32::hypot)),
+    ("min", Arity::AtLeast(1), |args, _| extreme("Math::min", &args, f32::min)),
+    ("max", Arity::AtLeast(1), |args, _| extreme("Math::max", &args, f32::max)),
+    ("clamp", Arity::Exact(3), |args, _| {
+      let [value, min, max] = numbers("Math::clamp", &args)?;
+      if min > max || min.is_nan() || max.is_nan() {
+        throw!(ErrorKind::Value, "Math::clamp needs a min that is at most the max, got {} and {}", min, max);
+      }
+      Ok(RuntimeValue::Number { value: value.clamp(min, max) })
+    }),
+    ("is_nan", Arity::Exact(1), |args, _| {
+      let [value] = numbers("Math::is_nan", &args)?;
+      Ok(RuntimeValue::Bool { value: value.is_nan() })
+    }),
+    ("is_finite", Arity::Exact(1), |args, _| {
+      let [value] = numbers("Math::is_finite", &args)?;
+      Ok(RuntimeValue::Bool { value: value.is_finite() })
+    })
+  ];
+  for (name, arity, body) in functions {
+    map.insert(name.to_string(), RuntimeValue::native(&format!("Math::{}", name), arity, body));
+  }
+  RuntimeValue::object(map)
+}
+
+fn unary(name: &str, args: &[RuntimeValue], op: fn(f32) -> f32) -> Eval {
+  let [x] = numbers(name, args)?;
+  Ok(RuntimeValue::Number { value: op(x) })
+}
+
+fn binary(name: &str, args: &[RuntimeValue], op: fn(f32, f32) -> f32) -> Eval {
+  let [x, y] = numbers(name, args)?;
+  Ok(RuntimeValue::Number { value: op(x, y) })
+}
+
+// `min` and `max` take one or more numbers, `Math::max(...values)` for an array.
+fn extreme(name: &str, args: &[RuntimeValue], pick: fn(f32, f32) -> f32) -> Eval {
+  let Some((first, rest)) = args.split_first() else {
+    throw!(ErrorKind::Argument, "{} expects at least one number", name);
+  };
+  let mut res = number(name, first)?;
+  for arg in rest {
+    let value = number(name, arg)?;
+    // NaN wins, where f32::min and f32::max would skip it
+    res = if res.is_nan() || value.is_nan() { f32::NAN } else { pick(res, value) };
+  }
+  Ok(RuntimeValue::Number { value: res })
+}
+
+// The arity of the native is checked before it is called, so there are `N` arguments.
+fn numbers<const N: usize>(name: &str, args: &[RuntimeValue]) -> Eval<[f32; N]> {
+  let mut res = [0.0; N];
+  for (slot, arg) in res.iter_mut().zip(args) {
+    *slot = number(name, arg)?;
+  }
+  Ok(res)
+}
+
+fn number(name: &str, arg: &RuntimeValue) -> Eval<f32> {
+  match arg {
+    RuntimeValue::Number { value } => Ok(*value),
+    other => throw!(ErrorKind::Argument, "{} expects numbers, not {} {:?}", name, other.type_name(), other)
+  }
+}
@@ -0,0 +1,288 @@
+use std::cell::RefCell;
+
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::errors::{error_with_cause, throw, ErrorKind, Eval};
+use crate::runtime::arrays::merge_sort;
+use crate::runtime::results;
+use crate::runtime::values::RuntimeValue;
+use crate::runtime::values::range::Range;
+use crate::runtime::iteration::{iterate, Filter, Map, Take};
+use crate::runtime::values::compare::{compare, equals};
+
+// Arrays are shared references, so mutating methods change the receiver in place
+// and every alias sees the change.
+pub type Method = fn(&RuntimeValue, Vec<RuntimeValue>, &mut Interpreter) -> Eval;
+
+pub fn lookup_method(receiver: &RuntimeValue, name: &str) -> Option<Method> {
+  match receiver {
+    RuntimeValue::Array { .. } => array_method(name),
+    RuntimeValue::String { .. } => string_method(name),
+    RuntimeValue::Number { .. } => number_method(name),
+    RuntimeValue::Range(_) => range_method(name),
+    RuntimeValue::Iterator(_) => iterator_method(name),
+    RuntimeValue::Enum(_) if results::is_option(receiver) => option_method(name),
+    RuntimeValue::Enum(_) if results::is_result(receiver) => result_method(name),
+    _ => None
+  }
+}
+
+// The helper namespace searched when a type has no built-in method of that name,
+// so `arr.concat(x)` resolves to `Array::concat(arr, x)`.
+pub fn helper_namespace(receiver: &RuntimeValue) -> Option<&'static str> {
+  match receiver {
+    RuntimeValue::Array { .. } => Some("Array"),
+    RuntimeValue::String { .. } => Some("String"),
+    RuntimeValue::Number { .. } => Some("Number"),
+    _ => None
+  }
+}
+
+fn array_method(name: &str) -> Option<Method> {
+  let method: Method = match name {
+    "len" => |receiver, _, _| Ok(RuntimeValue::Number { value: elements(receiver).borrow().len() as f32 }),
+    "is_empty" => |receiver, _, _| Ok(RuntimeValue::Bool { value: elements(receiver).borrow().is_empty() }),
+    "first" => |receiver, _, _| Ok(elements(receiver).borrow().first().cloned().unwrap_or(RuntimeValue::Null)),
+    "last" => |receiver, _, _| Ok(elements(receiver).borrow().last().cloned().unwrap_or(RuntimeValue::Null)),
+    "index_of" => |receiver, args, _| {
+      let value = match args.first() {
+        Some(value) => value,
+        _ => throw!(ErrorKind::Argument, "index_of expects a value to search for")
+      };
+      Ok(match elements(receiver).borrow().iter().position(|element| equals(element, value)) {
+        Some(index) => RuntimeValue::Number { value: index as f32 },
+        None => RuntimeValue::Null
+      })
+    },
+    "push" => |receiver, args, _| {
+      let mut elements = elements(receiver).borrow_mut();
+      elements.extend(args);
+      Ok(RuntimeValue::Number { value: elements.len() as f32 })
+    },
+    "pop" => |receiver, _, _| Ok(elements(receiver).borrow_mut().pop().unwrap_or(RuntimeValue::Null)),
+    "insert" => |receiver, args, _| {
+      let mut elements = elements(receiver).borrow_mut();
+      let index = index_arg(args.first(), elements.len() + 1, "insert")?;
+      let value = match args.get(1) {
+        Some(value) => value.clone(),
+        _ => throw!(ErrorKind::Argument, "insert expects a value as second argument")
+      };
+      elements.insert(index, value);
+      Ok(RuntimeValue::Null)
+    },
+    "remove" => |receiver, args, _| {
+      let mut elements = elements(receiver).borrow_mut();
+      let index = index_arg(args.first(), elements.len(), "remove")?;
+      Ok(elements.remove(index))
+    },
+    "sort" => |receiver, _, _| {
+      // the array is left as it was when two elements have no order
+      let sorted = merge_sort(elements(receiver).borrow().clone(), &mut |lhs, rhs| match compare(lhs, rhs) {
+        Some(ordering) => Ok(ordering),
+        None => throw!(ErrorKind::Type, "Cannot sort, {:?} and {:?} can't be compared", lhs, rhs)
+      })?;
+      *elements(receiver).borrow_mut() = sorted;
+      Ok(RuntimeValue::Null)
+    },
+    "reverse" => |receiver, _, _| {
+      elements(receiver).borrow_mut().reverse();
+      Ok(RuntimeValue::Null)
+    },
+    _ => return None
+  };
+  Some(method)
+}
+
+fn string_method(name: &str) -> Option<Method> {
+  let method: Method = match name {
+    "len" => |receiver, _, _| Ok(RuntimeValue::Number { value: string(receiver).chars().count() as f32 }),
+    "upper" => |receiver, _, _| Ok(RuntimeValue::String { value: string(receiver).to_uppercase() }),
+    "lower" => |receiver, _, _| Ok(RuntimeValue::String { value: string(receiver).to_lowercase() }),
+    "trim" => |receiver, _, _| Ok(RuntimeValue::String { value: string(receiver).trim().to_string() }),
+    _ => return None
+  };
+  Some(method)
+}
+
+fn number_method(name: &str) -> Option<Method> {
+  let method: Method = match name {
+    "round" => |receiver, _, _| Ok(RuntimeValue::Number { value: number(receiver).round() }),
+    "floor" => |receiver, _, _| Ok(RuntimeValue::Number { value: number(receiver).floor() }),
+    "ceil" => |receiver, _, _| Ok(RuntimeValue::Number { value: number(receiver).ceil() }),
+    "abs" => |receiver, _, _| Ok(RuntimeValue::Number { value: number(receiver).abs() }),
+    _ => return None
+  };
+  Some(method)
+}
+
+fn range_method(name: &str) -> Option<Method> {
+  let method: Method = match name {
+    "step" => |receiver, args, _| {
+      let step = match args.first() {
+        Some(RuntimeValue::Number { value }) if *value != 0.0 && value.is_finite() => *value,
+        Some(RuntimeValue::Number { value }) if *value == 0.0 => throw!(ErrorKind::Value, "step can't be 0"),
+        Some(RuntimeValue::Number { value }) => throw!(ErrorKind::Value, "step has to be finite, not {}", RuntimeValue::Number { value: *value }),
+        _ => throw!(ErrorKind::Argument, "step expects a number")
+      };
+      Ok(RuntimeValue::Range(Range { step, ..range(receiver) }))
+    },
+    "len" => |receiver, _, _| match range(receiver).len() {
+      Some(len) => Ok(RuntimeValue::Number { value: len as f32 }),
+      None => throw!(ErrorKind::Value, "{} has no length, it is missing an end", range(receiver))
+    },
+    "to_array" => |receiver, _, _| {
+      if range(receiver).end.is_none() {
+        throw!(ErrorKind::Value, "Cannot turn {} into an array, it has no end", range(receiver));
+      }
+      Ok(RuntimeValue::array(range(receiver).iter()?.map(|value| RuntimeValue::Number { value }).collect()))
+    },
+    // ranges are iterated lazily, `(0..10).map(f)` is an iterator
+    "map" | "filter" | "take" => return iterator_method(name),
+    _ => return None
+  };
+  Some(method)
+}
+
+// Iterators are lazy, `map`, `filter` and `take` return a new iterator that pulls
+// from the receiver only as its own values are asked for.
+fn iterator_method(name: &str) -> Option<Method> {
+  let method: Method = match name {
+    "next" => |receiver, _, interpreter| Ok(iterate(receiver)?.next(interpreter)?.unwrap_or(RuntimeValue::Done)),
+    "map" => |receiver, args, _| {
+      let func = function_arg(args, "map")?;
+      Ok(RuntimeValue::iterator(Map { source: iterate(receiver)?, func }))
+    },
+    "filter" => |receiver, args, _| {
+      let func = function_arg(args, "filter")?;
+      Ok(RuntimeValue::iterator(Filter { source: iterate(receiver)?, func }))
+    },
+    "take" => |receiver, args, _| {
+      let remaining = match args.first() {
+        Some(RuntimeValue::Number { value }) if *value >= 0.0 => *value as usize,
+        _ => throw!(ErrorKind::Argument, "take expects a number of values")
+      };
+      Ok(RuntimeValue::iterator(Take { source: iterate(receiver)?, remaining }))
+    },
+    "to_array" => |receiver, _, interpreter| {
+      let iterator = iterate(receiver)?;
+      let mut values = Vec::new();
+      while let Some(value) = iterator.next(interpreter)? {
+        values.push(value);
+      }
+      Ok(RuntimeValue::array(values))
+    },
+    _ => return None
+  };
+  Some(method)
+}
+
+fn option_method(name: &str) -> Option<Method> {
+  let method: Method = match name {
+    "is_some" => |receiver, _, _| Ok(RuntimeValue::Bool { value: outcome(receiver).is_ok() }),
+    "is_none" => |receiver, _, _| Ok(RuntimeValue::Bool { value: outcome(receiver).is_err() }),
+    _ => return outcome_method(name)
+  };
+  Some(method)
+}
+
+fn result_method(name: &str) -> Option<Method> {
+  let method: Method = match name {
+    "is_ok" => |receiver, _, _| Ok(RuntimeValue::Bool { value: outcome(receiver).is_ok() }),
+    "is_err" => |receiver, _, _| Ok(RuntimeValue::Bool { value: outcome(receiver).is_err() }),
+    "map_err" => |receiver, args, interpreter| match outcome(receiver) {
+      Ok(_) => Ok(receiver.clone()),
+      Err(_) => Ok(results::err(interpreter.call(&function_arg(args, "map_err")?, vec![payload(receiver)])?))
+    },
+    _ => return outcome_method(name)
+  };
+  Some(method)
+}
+
+// The methods `Option` and `Result` share. `Some` and `Ok` hold a value, `None`
+// and `Err` don't.
+fn outcome_method(name: &str) -> Option<Method> {
+  let method: Method = match name {
+    "unwrap" => |receiver, _, _| match outcome(receiver) {
+      Ok(value) => Ok(value),
+      Err(_) if results::is_option(receiver) => throw!(ErrorKind::Value, "Called unwrap on None"),
+      Err(_) => Err(error_with_cause(ErrorKind::Value, "Called unwrap on an Err".to_string(), payload(receiver)))
+    },
+    "unwrap_or" => |receiver, args, _| {
+      let Some(default) = args.into_iter().next() else {
+        throw!(ErrorKind::Argument, "unwrap_or expects a default value");
+      };
+      Ok(outcome(receiver).unwrap_or(default))
+    },
+    "map" => |receiver, args, interpreter| match outcome(receiver) {
+      Ok(value) => {
+        let value = interpreter.call(&function_arg(args, "map")?, vec![value])?;
+        Ok(if results::is_option(receiver) { results::some(value) } else { results::ok(value) })
+      },
+      Err(missing) => Ok(missing)
+    },
+    // like map, but the function returns an Option or Result of its own
+    "and_then" => |receiver, args, interpreter| match outcome(receiver) {
+      Ok(value) => interpreter.call(&function_arg(args, "and_then")?, vec![value]),
+      Err(missing) => Ok(missing)
+    },
+    _ => return None
+  };
+  Some(method)
+}
+
+fn elements(receiver: &RuntimeValue) -> &RefCell<Vec<RuntimeValue>> {
+  match receiver {
+    RuntimeValue::Array { elements } => elements,
+    _ => unreachable!("array method called on {:?}", receiver)
+  }
+}
+
+fn string(receiver: &RuntimeValue) -> &str {
+  match receiver {
+    RuntimeValue::String { value } => value,
+    _ => unreachable!("string method called on {:?}", receiver)
+  }
+}
+
+fn range(receiver: &RuntimeValue) -> Range {
+  match receiver {
+    RuntimeValue::Range(range) => *range,
+    _ => unreachable!("range method called on {:?}", receiver)
+  }
+}
+
+fn outcome(receiver: &RuntimeValue) -> Result<RuntimeValue, RuntimeValue> {
+  match results::split(receiver) {
+    Some(outcome) => outcome,
+    None => unreachable!("option or result method called on {:?}", receiver)
+  }
+}
+
+// The error an `Err` holds.
+fn payload(receiver: &RuntimeValue) -> RuntimeValue {
+  match receiver {
+    RuntimeValue::Enum(value) => value.borrow().values.first().cloned().unwrap_or(RuntimeValue::Null),
+    _ => unreachable!("result method called on {:?}", receiver)
+  }
+}
+
+fn number(receiver: &RuntimeValue) -> f32 {
+  match receiver {
+    RuntimeValue::Number { value } => *value,
+    _ => unreachable!("number method called on {:?}", receiver)
+  }
+}
+
+fn function_arg(args: Vec<RuntimeValue>, method: &str) -> Eval {
+  match args.into_iter().next() {
+    Some(func @ (RuntimeValue::Function { .. } | RuntimeValue::NativeFunction(_))) => Ok(func),
+    _ => throw!(ErrorKind::Argument, "{} expects a function", method)
+  }
+}
+
+fn index_arg(arg: Option<&RuntimeValue>, len: usize, method: &str) -> Eval<usize> {
+  match arg {
+    Some(RuntimeValue::Number { value }) if *value >= 0.0 && (*value as usize) < len => Ok(*value as usize),
+    Some(RuntimeValue::Number { value }) => throw!(ErrorKind::Index, "{} index {} out of bounds", method, value),
+    _ => throw!(ErrorKind::Argument, "{} expects a number as index", method)
+  }
+}
@@ -1,4 +1,15 @@
 pub mod environment;
+pub mod errors;
 pub mod values;
 pub mod interpreter;
-pub mod evaluate;
\ No newline at end of file
+pub mod evaluate;
+pub mod format;
+pub mod methods;
+pub mod iteration;
+pub mod results;
+pub mod arrays;
+pub mod math;
+pub mod strings;
+pub mod objects;
+pub mod modules;
+pub mod prelude;
@@ -0,0 +1,198 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::frontend::ast::{exported_names, ImportBindings};
+use crate::frontend::{lexer, parser};
+use crate::runtime::environment::Environment;
+use crate::runtime::errors::{throw, ErrorKind, Eval};
+use crate::runtime::interpreter::evaluate;
+use crate::runtime::prelude::global_environment;
+use crate::runtime::values::RuntimeValue;
+
+// Where the source of a module comes from. The interpreter reads files, a host that
+// embeds it can keep modules anywhere else by implementing this and calling `set_loader`.
+pub trait ModuleLoader {
+  // The name of the module `specifier` refers to in the module `importer`, or None
+  // when there is no such module. The main program imports with no importer. A module
+  // is cached by this name, so every specifier for it has to resolve to the same one.
+  fn resolve(&self, specifier: &str, importer: Option<&str>) -> Option<String>;
+  // The source of a module by the name `resolve` gave it.
+  fn load(&self, name: &str) -> Result<String, String>;
+}
+
+// Modules are files, named by their canonical path. A path is first looked for next
+// to the importing file, then in the search paths unless it starts with `./` or `../`.
+pub struct FileLoader {
+  search_paths: Vec<PathBuf>
+}
+
+impl FileLoader {
+  pub fn new(search_paths: Vec<PathBuf>) -> FileLoader {
+    FileLoader { search_paths }
+  }
+}
+
+impl ModuleLoader for FileLoader {
+  fn resolve(&self, specifier: &str, importer: Option<&str>) -> Option<String> {
+    let dir = importer.and_then(|importer| Path::new(importer).parent()).unwrap_or(Path::new("."));
+    let mut candidates = vec![dir.join(specifier)];
+    if !is_relative(specifier) {
+      candidates.extend(self.search_paths.iter().map(|search_path| search_path.join(specifier)));
+    }
+    let path = candidates.into_iter().find(|candidate| candidate.is_file())?;
+    Some(path.canonicalize().unwrap_or(path).display().to_string())
+  }
+
+  fn load(&self, name: &str) -> Result<String, String> {
+    fs::read_to_string(name).map_err(|err| err.to_string())
+  }
+}
+
+// Modules kept in memory by name, like `main.vig` and `lib/math.vig`. Imports that
+// start with `./` or `../` are relative to the importing module as with files, others
+// are the name itself.
+#[derive(Default)]
+pub struct MemoryLoader {
+  modules: HashMap<String, String>
+}
+
+impl MemoryLoader {
+  pub fn new() -> MemoryLoader {
+    MemoryLoader::default()
+  }
+
+  pub fn insert(&mut self, name: &str, source: &str) {
+    self.modules.insert(name.to_string(), source.to_string());
+  }
+}
+
+impl ModuleLoader for MemoryLoader {
+  fn resolve(&self, specifier: &str, importer: Option<&str>) -> Option<String> {
+    let mut parts: Vec<&str> = match importer {
+      Some(importer) if is_relative(specifier) => importer.split('/').collect(),
+      _ => vec![""]
+    };
+    parts.pop();
+    for part in specifier.split('/') {
+      match part {
+        "." | "" => (),
+        ".." => {
+          parts.pop()?;
+        },
+        part => parts.push(part)
+      }
+    }
+    let name = parts.join("/");
+    self.modules.contains_key(&name).then_some(name)
+  }
+
+  fn load(&self, name: &str) -> Result<String, String> {
+    self.modules.get(name).cloned().ok_or_else(|| format!("there is no module named {}", name))
+  }
+}
+
+fn is_relative(specifier: &str) -> bool {
+  specifier.starts_with("./") || specifier.starts_with("../")
+}
+
+// Every module has a global environment of its own. A module runs the first time it
+// is imported, later imports get the exports of that run.
+struct Modules {
+  loader: Rc<dyn ModuleLoader>,
+  // the exports of the modules that finished running, by name
+  cache: HashMap<String, RuntimeValue>,
+  // the modules that are running, each one imported by the one before it
+  loading: Vec<String>
+}
+
+thread_local! {
+  static MODULES: RefCell<Modules> = RefCell::new(Modules {
+    loader: Rc::new(FileLoader::new(Vec::new())),
+    cache: HashMap::new(),
+    loading: Vec::new()
+  });
+}
+
+pub fn set_loader(loader: Rc<dyn ModuleLoader>) {
+  MODULES.with(|modules| modules.borrow_mut().loader = loader);
+}
+
+// The module the program starts from, which its imports are relative to. Without
+// one, like in the repl, they are relative to the working directory.
+pub fn set_main(specifier: &str) {
+  let name = loader().resolve(specifier, None).unwrap_or_else(|| specifier.to_string());
+  MODULES.with(|modules| modules.borrow_mut().loading.push(name));
+}
+
+fn loader() -> Rc<dyn ModuleLoader> {
+  MODULES.with(|modules| modules.borrow().loader.clone())
+}
+
+pub fn evaluate_import(path: String, bindings: ImportBindings, env: &mut Environment) -> Eval {
+  let exports = import(&path)?;
+  match bindings {
+    ImportBindings::Module(name) => env.declare_var(name, exports, false),
+    ImportBindings::Names(names) => {
+      let RuntimeValue::Object(object) = &exports else { unreachable!() };
+      for name in names {
+        let value = object.borrow().properties.get(&name).cloned();
+        match value {
+          Some(value) => env.declare_var(name, value, false)?,
+          None => throw!(ErrorKind::Import, "{} has no export named {}", path, name)
+        };
+      }
+      Ok(RuntimeValue::Null)
+    }
+  }
+}
+
+fn import(specifier: &str) -> Eval {
+  let loader = loader();
+  let loading = MODULES.with(|modules| modules.borrow().loading.clone());
+  let Some(name) = loader.resolve(specifier, loading.last().map(String::as_str)) else {
+    throw!(ErrorKind::Import, "Cannot find module {:?}", specifier);
+  };
+  if let Some(exports) = MODULES.with(|modules| modules.borrow().cache.get(&name).cloned()) {
+    return Ok(exports);
+  }
+  if let Some(start) = loading.iter().position(|module| *module == name) {
+    let chain: Vec<String> = loading[start..].iter().chain([&name]).map(|module| show(module)).collect();
+    throw!(ErrorKind::Import, "Import cycle: {}", chain.join(" -> "));
+  }
+
+  let source = match loader.load(&name) {
+    Ok(source) => source,
+    Err(err) => throw!(ErrorKind::IO, "Cannot read module {}: {}", show(&name), err)
+  };
+  let tokens = lexer::tokenize(&source);
+  let program = parser::Parser::new(&tokens).produce_ast();
+  let names = exported_names(&program.body);
+
+  MODULES.with(|modules| modules.borrow_mut().loading.push(name.clone()));
+  let mut env = global_environment();
+  let res = evaluate(program, &mut env);
+  MODULES.with(|modules| modules.borrow_mut().loading.pop());
+  res?;
+
+  let mut exports = HashMap::new();
+  for name in names {
+    let value = env.lookup_var(name.clone())?;
+    exports.insert(name, value);
+  }
+  // frozen, so an importer can't change what every other importer of the module sees
+  let exports = RuntimeValue::object(exports);
+  if let RuntimeValue::Object(object) = &exports {
+    object.borrow_mut().frozen = true;
+  }
+  MODULES.with(|modules| modules.borrow_mut().cache.insert(name, exports.clone()));
+  Ok(exports)
+}
+
+// Files in errors are relative to the working directory when they are inside it.
+fn show(name: &str) -> String {
+  let relative = std::env::current_dir().ok().and_then(|dir| Path::new(name).strip_prefix(dir).ok().map(Path::to_path_buf));
+  relative.map_or_else(|| name.to_string(), |path| path.display().to_string())
+}
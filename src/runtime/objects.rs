@@ -0,0 +1,146 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::runtime::errors::{throw, ErrorKind, Eval};
+use crate::runtime::values::{Arity, NativeBody, Object, RuntimeValue};
+
+// The natives of the `Object` helper. Objects don't have them as methods, since
+// `obj.keys()` would hide a `keys` property of the object. The keys, values and
+// entries come out sorted by key, like iterating over an object.
+pub fn object_object() -> RuntimeValue {
+  let functions: [(&str, Arity, NativeBody); 9] = [
+    ("keys", Arity::Exact(1), |args, _| {
+      let object = object_arg("Object::keys", &args)?;
+      let keys = sorted_keys(&object.borrow()).into_iter().map(|key| RuntimeValue::String { value: key });
+      Ok(RuntimeValue::array(keys.collect()))
+    }),
+    ("values", Arity::Exact(1), |args, _| {
+      let object = object_arg("Object::values", &args)?;
+      let object = object.borrow();
+      let values = sorted_keys(&object).into_iter().map(|key| object.properties[&key].clone()).collect();
+      Ok(RuntimeValue::array(values))
+    }),
+    ("entries", Arity::Exact(1), |args, _| {
+      let object = object_arg("Object::entries", &args)?;
+      let object = object.borrow();
+      let entries = sorted_keys(&object).into_iter().map(|key| {
+        let value = object.properties[&key].clone();
+        RuntimeValue::array(vec![RuntimeValue::String { value: key }, value])
+      }).collect();
+      Ok(RuntimeValue::array(entries))
+    }),
+    // the opposite of `entries`, a later entry with the same key wins
+    ("from_entries", Arity::Exact(1), |args, _| {
+      let entries = match &args[0] {
+        RuntimeValue::Array { elements } => elements.borrow().clone(),
+        other => throw!(ErrorKind::Argument, "Object::from_entries expects an array of [key, value] pairs, not {:?}", other)
+      };
+      let mut properties = HashMap::new();
+      for entry in entries {
+        let pair = match &entry {
+          RuntimeValue::Array { elements } => elements.borrow().clone(),
+          _ => Vec::new()
+        };
+        match pair.as_slice() {
+          [RuntimeValue::String { value: key }, value] => properties.insert(key.clone(), value.clone()),
+          _ => throw!(ErrorKind::Argument, "Object::from_entries expects [key, value] pairs with a string key, not {:?}", entry)
+        };
+      }
+      Ok(RuntimeValue::object(properties))
+    }),
+    ("has", Arity::Exact(2), |args, _| {
+      let object = object_arg("Object::has", &args)?;
+      let key = key_arg("Object::has", &args)?;
+      Ok(RuntimeValue::Bool { value: object.borrow().properties.contains_key(key) })
+    }),
+    // removes the key and returns its value, or null when it isn't there
+    ("remove", Arity::Exact(2), |args, _| {
+      let object = object_arg("Object::remove", &args)?;
+      let key = key_arg("Object::remove", &args)?;
+      let mut object = object.borrow_mut();
+      if object.frozen {
+        throw!(ErrorKind::Type, "Can't remove {} from a frozen object", key);
+      }
+      Ok(object.properties.remove(key).unwrap_or(RuntimeValue::Null))
+    }),
+    // `merge(lhs, rhs)` is a new object with the properties of both, those of `rhs`
+    // win. `merge(lhs, rhs, true)` merges objects both have under a key as well.
+    ("merge", Arity::Between(2, 3), |args, _| {
+      let lhs = object_arg("Object::merge", &args)?;
+      let rhs = match &args[1] {
+        RuntimeValue::Object(object) => object,
+        other => throw!(ErrorKind::Argument, "Object::merge expects an object as second argument, not {:?}", other)
+      };
+      let deep = match args.get(2) {
+        Some(RuntimeValue::Bool { value }) => *value,
+        None => false,
+        Some(other) => throw!(ErrorKind::Argument, "Object::merge expects a bool as third argument, not {:?}", other)
+      };
+      Ok(RuntimeValue::object(merge(lhs, rhs, deep, &mut Vec::new())?))
+    }),
+    ("len", Arity::Exact(1), |args, _| {
+      let object = object_arg("Object::len", &args)?;
+      Ok(RuntimeValue::Number { value: object.borrow().properties.len() as f32 })
+    }),
+    // after `freeze(obj)` assigning to or removing a property of the object is a
+    // TypeError. Objects in it aren't frozen.
+    ("freeze", Arity::Exact(1), |args, _| {
+      let object = object_arg("Object::freeze", &args)?;
+      object.borrow_mut().frozen = true;
+      Ok(args[0].clone())
+    })
+  ];
+  let mut map = HashMap::new();
+  for (name, arity, body) in functions {
+    map.insert(name.to_string(), RuntimeValue::native(&format!("Object::{}", name), arity, body));
+  }
+  RuntimeValue::object(map)
+}
+
+fn object_arg<'a>(name: &str, args: &'a [RuntimeValue]) -> Eval<&'a Rc<RefCell<Object>>> {
+  match &args[0] {
+    RuntimeValue::Object(object) => Ok(object),
+    other => throw!(ErrorKind::Argument, "{} expects an object as first argument, not {:?}", name, other)
+  }
+}
+
+fn key_arg<'a>(name: &str, args: &'a [RuntimeValue]) -> Eval<&'a String> {
+  match &args[1] {
+    RuntimeValue::String { value } => Ok(value),
+    other => throw!(ErrorKind::Argument, "{} expects a string key as second argument, not {:?}", name, other)
+  }
+}
+
+fn sorted_keys(object: &Object) -> Vec<String> {
+  let mut keys: Vec<String> = object.properties.keys().cloned().collect();
+  keys.sort();
+  keys
+}
+
+// `merging` holds the pairs of objects being merged deeply, so objects that contain
+// themselves are an error rather than endless.
+fn merge(
+  lhs: &Rc<RefCell<Object>>,
+  rhs: &Rc<RefCell<Object>>,
+  deep: bool,
+  merging: &mut Vec<(*const RefCell<Object>, *const RefCell<Object>)>
+) -> Eval<HashMap<String, RuntimeValue>> {
+  let pair = (Rc::as_ptr(lhs), Rc::as_ptr(rhs));
+  if merging.contains(&pair) {
+    throw!(ErrorKind::Value, "Object::merge can't deeply merge objects that contain themselves");
+  }
+  merging.push(pair);
+  let mut properties = lhs.borrow().properties.clone();
+  for (key, value) in rhs.borrow().properties.iter() {
+    let merged = match (properties.get(key), value) {
+      (Some(RuntimeValue::Object(inner_lhs)), RuntimeValue::Object(inner_rhs)) if deep => {
+        RuntimeValue::object(merge(inner_lhs, inner_rhs, deep, merging)?)
+      },
+      _ => value.clone()
+    };
+    properties.insert(key.clone(), merged);
+  }
+  merging.pop();
+  Ok(properties)
+}
@@ -0,0 +1,54 @@
+use std::cell::Cell;
+
+use crate::frontend::ast::{exported_names, Program, Stmt};
+use crate::frontend::{lexer, parser};
+use crate::runtime::environment::{create_global_environment, Environment};
+use crate::runtime::errors::{describe_uncaught, Eval, Interrupt};
+use crate::runtime::interpreter::evaluate;
+
+// The part of the standard library that is written in vigscript. It is bundled into
+// the binary and runs in every global environment, before the file that gets it.
+const PRELUDE: &[(&str, &str)] = &[
+  ("array.vig", include_str!("prelude/array.vig"))
+];
+
+thread_local! {
+  static ENABLED: Cell<bool> = const { Cell::new(true) };
+  // parsed once, every module runs it again in its own global environment
+  static PROGRAMS: Vec<(&'static str, Vec<Stmt>)> = PRELUDE.iter().map(|(name, source)| {
+    let tokens = lexer::tokenize(source);
+    (*name, parser::Parser::new(&tokens).produce_ast().body)
+  }).collect();
+}
+
+// `--no-prelude`, global environments only get the natives after this.
+pub fn disable() {
+  ENABLED.with(|enabled| enabled.set(false));
+}
+
+// The natives, and then the prelude unless it is disabled. Every file of the prelude
+// runs in a scope of its own, only what it exports is declared globally.
+pub fn global_environment() -> Environment {
+  let mut env = create_global_environment();
+  if ENABLED.with(Cell::get) {
+    PROGRAMS.with(|programs| {
+      for (name, body) in programs {
+        if let Err(Interrupt::Throw(value) | Interrupt::Return(value)) = load(body.clone(), &mut env) {
+          panic!("The prelude file {} failed to load\n{}", name, describe_uncaught(&value));
+        }
+      }
+    });
+  }
+  env
+}
+
+fn load(body: Vec<Stmt>, env: &mut Environment) -> Eval<()> {
+  let names = exported_names(&body);
+  let mut scope = Environment::new(Some(env.clone()));
+  evaluate(Program { body }, &mut scope)?;
+  for name in names {
+    let value = scope.lookup_var(name.clone())?;
+    env.declare_var(name, value, false)?;
+  }
+  Ok(())
+}
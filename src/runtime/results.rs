@@ -0,0 +1,71 @@
+use std::rc::Rc;
+
+use crate::frontend::ast::Variant;
+use crate::runtime::values::{EnumType, RuntimeValue};
+
+// `Option` and `Result`, the built-in enums for failures a script is expected to
+// handle, like a string that isn't a number. `Some`, `None`, `Ok` and `Err` are
+// declared as globals so they can be used without the enum name.
+thread_local! {
+  static OPTION_TYPE: Rc<EnumType> = builtin_enum("Option", &[("Some", &["value"]), ("None", &[])]);
+  static RESULT_TYPE: Rc<EnumType> = builtin_enum("Result", &[("Ok", &["value"]), ("Err", &["error"])]);
+}
+
+fn builtin_enum(name: &str, variants: &[(&str, &[&str])]) -> Rc<EnumType> {
+  let variants = variants.iter().map(|(name, fields)| Variant {
+    name: name.to_string(),
+    fields: fields.iter().map(|field| field.to_string()).collect()
+  }).collect();
+  match RuntimeValue::enum_type(name.to_string(), variants) {
+    RuntimeValue::EnumType(enum_type) => enum_type,
+    _ => unreachable!()
+  }
+}
+
+pub fn option_type() -> Rc<EnumType> {
+  OPTION_TYPE.with(|option_type| option_type.clone())
+}
+
+pub fn result_type() -> Rc<EnumType> {
+  RESULT_TYPE.with(|result_type| result_type.clone())
+}
+
+pub fn some(value: RuntimeValue) -> RuntimeValue {
+  RuntimeValue::enum_value(option_type(), 0, vec![value])
+}
+
+pub fn none() -> RuntimeValue {
+  RuntimeValue::enum_value(option_type(), 1, Vec::new())
+}
+
+pub fn ok(value: RuntimeValue) -> RuntimeValue {
+  RuntimeValue::enum_value(result_type(), 0, vec![value])
+}
+
+pub fn err(error: RuntimeValue) -> RuntimeValue {
+  RuntimeValue::enum_value(result_type(), 1, vec![error])
+}
+
+pub fn is_option(value: &RuntimeValue) -> bool {
+  matches!(value, RuntimeValue::Enum(value) if Rc::ptr_eq(&value.borrow().enum_type, &option_type()))
+}
+
+pub fn is_result(value: &RuntimeValue) -> bool {
+  matches!(value, RuntimeValue::Enum(value) if Rc::ptr_eq(&value.borrow().enum_type, &result_type()))
+}
+
+// `Some(v)` and `Ok(v)` give `Ok(v)`, `None` and `Err(e)` give `Err` with the value
+// itself. `None` for anything that isn't an `Option` or a `Result`.
+pub fn split(value: &RuntimeValue) -> Option<Result<RuntimeValue, RuntimeValue>> {
+  if !is_option(value) && !is_result(value) {
+    return None;
+  }
+  let RuntimeValue::Enum(inner) = value else {
+    unreachable!()
+  };
+  let inner = inner.borrow();
+  Some(match inner.variant {
+    0 => Ok(inner.values[0].clone()),
+    _ => Err(value.clone())
+  })
+}
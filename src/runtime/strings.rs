@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::num::IntErrorKind;
+
+use crate::runtime::errors::{error_value, throw, ErrorKind, Eval};
+use crate::runtime::results;
+use crate::runtime::values::{Arity, NativeBody, RuntimeValue};
+
+// The longest string `repeat` and the pad helpers make, in bytes. A count or width
+// from a script can be any number, which must be an error rather than an abort.
+const MAX_LENGTH: usize = 1 << 28;
+
+// The `String` helpers, which strings also have as methods, `"a,b".split(",")`.
+// Lengths and indices count characters, not bytes, so `"héllo".len()` is 5.
+pub fn string_object() -> RuntimeValue {
+  let functions: [(&str, Arity, NativeBody); 21] = [
+    ("len", Arity::Exact(1), |args, _| Ok(number(text("String::len", &args, 0)?.chars().count()))),
+    ("upper", Arity::Exact(1), |args, _| Ok(string(text("String::upper", &args, 0)?.to_uppercase()))),
+    ("lower", Arity::Exact(1), |args, _| Ok(string(text("String::lower", &args, 0)?.to_lowercase()))),
+    ("trim", Arity::Exact(1), |args, _| Ok(string(text("String::trim", &args, 0)?.trim()))),
+    ("trim_start", Arity::Exact(1), |args, _| Ok(string(text("String::trim_start", &args, 0)?.trim_start()))),
+    ("trim_end", Arity::Exact(1), |args, _| Ok(string(text("String::trim_end", &args, 0)?.trim_end()))),
+    ("reverse", Arity::Exact(1), |args, _| Ok(string(text("String::reverse", &args, 0)?.chars().rev().collect::<String>()))),
+    ("chars", Arity::Exact(1), |args, _| {
+      let chars = text("String::chars", &args, 0)?.chars().map(|ch| string(ch.to_string())).collect();
+      Ok(RuntimeValue::array(chars))
+    }),
+    // without a separator the string is split on whitespace, an empty separator
+    // splits it into characters
+    ("split", Arity::Between(1, 2), |args, _| {
+      let value = text("String::split", &args, 0)?;
+      let parts: Vec<RuntimeValue> = match args.get(1) {
+        None | Some(RuntimeValue::Null) => value.split_whitespace().map(string).collect(),
+        Some(_) => match text("String::split", &args, 1)? {
+          "" => value.chars().map(|ch| string(ch.to_string())).collect(),
+          separator => value.split(separator).map(string).collect()
+        }
+      };
+      Ok(RuntimeValue::array(parts))
+    }),
+    // values that aren't strings are joined the way `print!` shows them
+    ("join", Arity::Between(1, 2), |args, _| {
+      let Some(RuntimeValue::Array { elements }) = args.first() else {
+        throw!(ErrorKind::Argument, "String::join expects an array of the parts to join");
+      };
+      let separator = optional_text("String::join", &args, 1, "")?;
+      let parts: Vec<String> = elements.borrow().iter().map(|part| part.to_string()).collect();
+      Ok(string(parts.join(separator)))
+    }),
+    ("replace", Arity::Exact(3), |args, _| {
+      let value = text("String::replace", &args, 0)?;
+      let from = text("String::replace", &args, 1)?;
+      let to = text("String::replace", &args, 2)?;
+      if from.is_empty() {
+        throw!(ErrorKind::Value, "String::replace can't replace an empty string");
+      }
+      Ok(string(value.replace(from, to)))
+    }),
+    // the character index of the first occurrence, or null
+    ("find", Arity::Exact(2), |args, _| {
+      let value = text("String::find", &args, 0)?;
+      let search = text("String::find", &args, 1)?;
+      Ok(match value.find(search) {
+        Some(byte) => number(value[..byte].chars().count()),
+        None => RuntimeValue::Null
+      })
+    }),
+    ("contains", Arity::Exact(2), |args, _| {
+      let value = text("String::contains", &args, 0)?;
+      Ok(RuntimeValue::Bool { value: value.contains(text("String::contains", &args, 1)?) })
+    }),
+    ("starts_with", Arity::Exact(2), |args, _| {
+      let value = text("String::starts_with", &args, 0)?;
+      Ok(RuntimeValue::Bool { value: value.starts_with(text("String::starts_with", &args, 1)?) })
+    }),
+    ("ends_with", Arity::Exact(2), |args, _| {
+      let value = text("String::ends_with", &args, 0)?;
+      Ok(RuntimeValue::Bool { value: value.ends_with(text("String::ends_with", &args, 1)?) })
+    }),
+    // `substring(s, start, end)` from the character at `start` up to the one at `end`,
+    // or to the end of the string when it is left out
+    ("substring", Arity::Between(2, 3), |args, _| {
+      let value = text("String::substring", &args, 0)?;
+      let len = value.chars().count();
+      let start = whole("String::substring", &args, 1)?;
+      let end = match args.get(2) {
+        None | Some(RuntimeValue::Null) => len,
+        Some(_) => whole("String::substring", &args, 2)?
+      };
+      if start > end || end > len {
+        throw!(ErrorKind::Index, "Cannot take the characters {} to {} of a string of length {}", start, end, len);
+      }
+      Ok(string(value.chars().skip(start).take(end - start).collect::<String>()))
+    }),
+    ("repeat", Arity::Exact(2), |args, _| {
+      let value = text("String::repeat", &args, 0)?;
+      let count = whole("String::repeat", &args, 1)?;
+      match value.len().checked_mul(count) {
+        Some(len) if len <= MAX_LENGTH => Ok(string(value.repeat(count))),
+        _ => throw!(ErrorKind::Value, "String::repeat can't make a string longer than {} bytes", MAX_LENGTH)
+      }
+    }),
+    ("pad_start", Arity::Between(2, 3), |args, _| pad("String::pad_start", &args, true)),
+    ("pad_end", Arity::Between(2, 3), |args, _| pad("String::pad_end", &args, false)),
+    ("parse_number", Arity::Exact(1), |args, _| Ok(parse_number(text("String::parse_number", &args, 0)?))),
+    // `parse_int(s, radix)` with a radix from 2 to 36, 10 when it is left out
+    ("parse_int", Arity::Between(1, 2), |args, _| {
+      let value = text("String::parse_int", &args, 0)?;
+      let radix = match args.get(1) {
+        None | Some(RuntimeValue::Null) => 10,
+        Some(_) => whole("String::parse_int", &args, 1)?
+      };
+      if !(2..=36).contains(&radix) {
+        throw!(ErrorKind::Argument, "String::parse_int expects a radix from 2 to 36, not {}", radix);
+      }
+      Ok(match i64::from_str_radix(value.trim(), radix as u32) {
+        Ok(value) => results::ok(RuntimeValue::Number { value: value as f32 }),
+        Err(err) if matches!(err.kind(), IntErrorKind::PosOverflow | IntErrorKind::NegOverflow) => {
+          results::err(value_error(format!("{:?} is out of range, whole numbers go up to {} in magnitude", value, i64::MAX)))
+        },
+        Err(_) => results::err(value_error(format!("{:?} is not a whole number in base {}", value, radix)))
+      })
+    })
+  ];
+  let mut map = HashMap::new();
+  for (name, arity, body) in functions {
+    map.insert(name.to_string(), RuntimeValue::native(&format!("String::{}", name), arity, body));
+  }
+  RuntimeValue::object(map)
+}
+
+// `Ok(number)`, or `Err` with a `ValueError` when the text isn't a number.
+pub fn parse_number(text: &str) -> RuntimeValue {
+  match text.trim().parse::<f32>() {
+    Ok(value) => results::ok(RuntimeValue::Number { value }),
+    Err(_) => results::err(value_error(format!("{:?} is not a number", text)))
+  }
+}
+
+// `pad_start(s, width, fill)` adds `fill`, a space by default, until the string is
+// `width` characters long.
+fn pad(name: &str, args: &[RuntimeValue], start: bool) -> Eval {
+  let value = text(name, args, 0)?;
+  let width = whole(name, args, 1)?;
+  if width > MAX_LENGTH {
+    throw!(ErrorKind::Value, "{} can't pad to more than {} characters", name, MAX_LENGTH);
+  }
+  let fill = optional_text(name, args, 2, " ")?;
+  if fill.is_empty() {
+    throw!(ErrorKind::Value, "{} can't pad with an empty string", name);
+  }
+  let padding: String = fill.chars().cycle().take(width.saturating_sub(value.chars().count())).collect();
+  Ok(string(if start { padding + value } else { value.to_string() + &padding }))
+}
+
+fn text<'a>(name: &str, args: &'a [RuntimeValue], index: usize) -> Eval<&'a str> {
+  match args.get(index) {
+    Some(RuntimeValue::String { value }) => Ok(value),
+    Some(other) => throw!(ErrorKind::Argument, "{} expects a string as argument {}, not {} {:?}", name, index + 1, other.type_name(), other),
+    None => throw!(ErrorKind::Argument, "{} expects a string as argument {}", name, index + 1)
+  }
+}
+
+fn optional_text<'a>(name: &str, args: &'a [RuntimeValue], index: usize, default: &'a str) -> Eval<&'a str> {
+  match args.get(index) {
+    None | Some(RuntimeValue::Null) => Ok(default),
+    Some(_) => text(name, args, index)
+  }
+}
+
+fn whole(name: &str, args: &[RuntimeValue], index: usize) -> Eval<usize> {
+  match args.get(index) {
+    Some(RuntimeValue::Number { value }) if *value >= 0.0 && value.fract() == 0.0 => Ok(*value as usize),
+    Some(other) => throw!(ErrorKind::Argument, "{} expects a whole number of at least 0 as argument {}, not {:?}", name, index + 1, other),
+    None => throw!(ErrorKind::Argument, "{} expects a number as argument {}", name, index + 1)
+  }
+}
+
+fn value_error(message: String) -> RuntimeValue {
+  error_value(ErrorKind::Value.to_string(), message, RuntimeValue::Null)
+}
+
+fn string(value: impl Into<String>) -> RuntimeValue {
+  RuntimeValue::String { value: value.into() }
+}
+
+fn number(value: usize) -> RuntimeValue {
+  RuntimeValue::Number { value: value as f32 }
+}
@@ -0,0 +1,124 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use crate::runtime::values::RuntimeValue;
+
+// Structural equality. Arrays are equal when their elements are, objects when they
+// have the same keys with equal values, struct values when they have the same
+// struct type and equal fields, enum values when they are the same variant with an
+// equal payload. Functions and types are only equal to themselves.
+pub fn equals(lhs: &RuntimeValue, rhs: &RuntimeValue) -> bool {
+  equals_inner(lhs, rhs, &mut Vec::new())
+}
+
+// The ordering used by the comparison operators and by sorting. Numbers, strings
+// and booleans order by value, strings lexicographically, and arrays element by
+// element with the shorter array first when one is a prefix of the other.
+// Returns `None` for values that have no order, like a number and a string.
+pub fn compare(lhs: &RuntimeValue, rhs: &RuntimeValue) -> Option<Ordering> {
+  compare_inner(lhs, rhs, &mut Vec::new())
+}
+
+// `seen` holds the pairs of arrays and objects currently being compared. Meeting a
+// pair again means both sides are cyclic in the same way, which counts as equal.
+type Seen = Vec<(*const (), *const ())>;
+
+fn equals_inner(lhs: &RuntimeValue, rhs: &RuntimeValue, seen: &mut Seen) -> bool {
+  match (lhs, rhs) {
+    (RuntimeValue::Array { elements: lhs }, RuntimeValue::Array { elements: rhs }) => {
+      let pair = (Rc::as_ptr(lhs) as *const (), Rc::as_ptr(rhs) as *const ());
+      if Rc::ptr_eq(lhs, rhs) || seen.contains(&pair) {
+        return true;
+      }
+      seen.push(pair);
+      let (lhs, rhs) = (lhs.borrow(), rhs.borrow());
+      let equal = lhs.len() == rhs.len() && lhs.iter().zip(rhs.iter()).all(|(lhs, rhs)| equals_inner(lhs, rhs, seen));
+      seen.pop();
+      equal
+    },
+    (RuntimeValue::Object(lhs), RuntimeValue::Object(rhs)) => {
+      let pair = (Rc::as_ptr(lhs) as *const (), Rc::as_ptr(rhs) as *const ());
+      if Rc::ptr_eq(lhs, rhs) || seen.contains(&pair) {
+        return true;
+      }
+      seen.push(pair);
+      let (lhs, rhs) = (lhs.borrow(), rhs.borrow());
+      let equal = lhs.properties.len() == rhs.properties.len() && lhs.properties.iter().all(|(key, lhs)| {
+        match rhs.properties.get(key) {
+          Some(rhs) => equals_inner(lhs, rhs, seen),
+          None => false
+        }
+      });
+      seen.pop();
+      equal
+    },
+    (RuntimeValue::Struct(lhs), RuntimeValue::Struct(rhs)) => {
+      let pair = (Rc::as_ptr(lhs) as *const (), Rc::as_ptr(rhs) as *const ());
+      if Rc::ptr_eq(lhs, rhs) || seen.contains(&pair) {
+        return true;
+      }
+      seen.push(pair);
+      let (lhs, rhs) = (lhs.borrow(), rhs.borrow());
+      let equal = Rc::ptr_eq(&lhs.struct_type, &rhs.struct_type)
+        && lhs.fields.iter().zip(rhs.fields.iter()).all(|(lhs, rhs)| equals_inner(lhs, rhs, seen));
+      seen.pop();
+      equal
+    },
+    (RuntimeValue::StructType(lhs), RuntimeValue::StructType(rhs)) => Rc::ptr_eq(lhs, rhs),
+    (RuntimeValue::Enum(lhs), RuntimeValue::Enum(rhs)) => {
+      let pair = (Rc::as_ptr(lhs) as *const (), Rc::as_ptr(rhs) as *const ());
+      if Rc::ptr_eq(lhs, rhs) || seen.contains(&pair) {
+        return true;
+      }
+      seen.push(pair);
+      let (lhs, rhs) = (lhs.borrow(), rhs.borrow());
+      let equal = Rc::ptr_eq(&lhs.enum_type, &rhs.enum_type) && lhs.variant == rhs.variant
+        && lhs.values.iter().zip(rhs.values.iter()).all(|(lhs, rhs)| equals_inner(lhs, rhs, seen));
+      seen.pop();
+      equal
+    },
+    (RuntimeValue::EnumType(lhs), RuntimeValue::EnumType(rhs)) => Rc::ptr_eq(lhs, rhs),
+    (RuntimeValue::Variant { .. }, RuntimeValue::Variant { .. }) => lhs == rhs,
+    (RuntimeValue::Number { value: lhs }, RuntimeValue::Number { value: rhs }) => lhs == rhs,
+    (RuntimeValue::String { value: lhs }, RuntimeValue::String { value: rhs }) => lhs == rhs,
+    (RuntimeValue::Bool { value: lhs }, RuntimeValue::Bool { value: rhs }) => lhs == rhs,
+    (RuntimeValue::Null, RuntimeValue::Null) => true,
+    (RuntimeValue::Range(lhs), RuntimeValue::Range(rhs)) => lhs == rhs,
+    (RuntimeValue::Iterator(lhs), RuntimeValue::Iterator(rhs)) => Rc::ptr_eq(lhs, rhs),
+    (RuntimeValue::Done, RuntimeValue::Done) => true,
+    (RuntimeValue::Function { .. }, RuntimeValue::Function { .. }) => lhs == rhs,
+    (RuntimeValue::NativeFunction(lhs), RuntimeValue::NativeFunction(rhs)) => Rc::ptr_eq(lhs, rhs),
+    _ => false
+  }
+}
+
+fn compare_inner(lhs: &RuntimeValue, rhs: &RuntimeValue, seen: &mut Seen) -> Option<Ordering> {
+  match (lhs, rhs) {
+    (RuntimeValue::Number { value: lhs }, RuntimeValue::Number { value: rhs }) => lhs.partial_cmp(rhs),
+    (RuntimeValue::String { value: lhs }, RuntimeValue::String { value: rhs }) => Some(lhs.cmp(rhs)),
+    (RuntimeValue::Bool { value: lhs }, RuntimeValue::Bool { value: rhs }) => Some(lhs.cmp(rhs)),
+    (RuntimeValue::Null, RuntimeValue::Null) => Some(Ordering::Equal),
+    (RuntimeValue::Array { elements: lhs }, RuntimeValue::Array { elements: rhs }) => {
+      let pair = (Rc::as_ptr(lhs) as *const (), Rc::as_ptr(rhs) as *const ());
+      if Rc::ptr_eq(lhs, rhs) || seen.contains(&pair) {
+        return Some(Ordering::Equal);
+      }
+      seen.push(pair);
+      let (lhs, rhs) = (lhs.borrow(), rhs.borrow());
+      let mut ordering = Some(lhs.len().cmp(&rhs.len()));
+      for (lhs, rhs) in lhs.iter().zip(rhs.iter()) {
+        match compare_inner(lhs, rhs, seen) {
+          Some(Ordering::Equal) => continue,
+          other => {
+            ordering = other;
+            break;
+          }
+        }
+      }
+      seen.pop();
+      ordering
+    },
+    (lhs, rhs) if equals_inner(lhs, rhs, seen) => Some(Ordering::Equal),
+    _ => None
+  }
+}
@@ -0,0 +1,199 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::frontend::ast;
+use crate::runtime::values::{Arity, RuntimeValue};
+
+// Values are shown in vigscript literal syntax, `{:#}` spreads arrays and objects
+// over multiple lines. Display leaves a string on its own unquoted so it prints as
+// text, Debug quotes it. Strings nested in arrays and objects are always quoted.
+impl fmt::Display for RuntimeValue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    Printer { pretty: f.alternate(), seen: Vec::new() }.write(f, self, 0, false)
+  }
+}
+
+impl fmt::Debug for RuntimeValue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    Printer { pretty: f.alternate(), seen: Vec::new() }.write(f, self, 0, true)
+  }
+}
+
+// For errors about calls, "1 argument", "1 to 3 arguments" or "at least 1 argument".
+impl fmt::Display for Arity {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let (text, last) = match *self {
+      Arity::Exact(arity) => (arity.to_string(), arity),
+      Arity::Between(min, max) => (format!("{} to {}", min, max), max),
+      Arity::AtLeast(min) => (format!("at least {}", min), min)
+    };
+    write!(f, "{} argument{}", text, if last == 1 { "" } else { "s" })
+  }
+}
+
+struct Printer {
+  pretty: bool,
+  // the arrays and objects currently being written, to spot cycles
+  seen: Vec<*const ()>
+}
+
+impl Printer {
+  fn write(&mut self, f: &mut fmt::Formatter<'_>, value: &RuntimeValue, depth: usize, quoted: bool) -> fmt::Result {
+    match value {
+      RuntimeValue::Null => write!(f, "null"),
+      RuntimeValue::Bool { value } => write!(f, "{}", value),
+      RuntimeValue::Number { value } => write_number(f, *value),
+      RuntimeValue::String { value } if quoted => write!(f, "\"{}\"", value.escape_debug()),
+      RuntimeValue::String { value } => write!(f, "{}", value),
+      RuntimeValue::Function { name, params, .. } => write!(f, "<func {}({})>", name, ast::join(params)),
+      RuntimeValue::NativeFunction(native) => write!(f, "<native func {}>", native.name),
+      RuntimeValue::Range(range) => write!(f, "{}", range),
+      RuntimeValue::Iterator(_) => write!(f, "<iterator>"),
+      RuntimeValue::Done => write!(f, "done"),
+      RuntimeValue::Array { elements } => {
+        let ptr = Rc::as_ptr(elements) as *const ();
+        if self.seen.contains(&ptr) {
+          return write!(f, "[...]");
+        }
+        let elements = elements.borrow();
+        if elements.is_empty() {
+          return write!(f, "[]");
+        }
+        self.seen.push(ptr);
+        write!(f, "[")?;
+        for (i, element) in elements.iter().enumerate() {
+          self.separator(f, i, depth + 1)?;
+          self.write(f, element, depth + 1, true)?;
+        }
+        self.close(f, depth, "]")?;
+        self.seen.pop();
+        Ok(())
+      },
+      RuntimeValue::StructType(struct_type) => write!(f, "<struct {}>", struct_type.name),
+      RuntimeValue::Struct(value) => {
+        let ptr = Rc::as_ptr(value) as *const ();
+        let value = value.borrow();
+        if self.seen.contains(&ptr) {
+          return write!(f, "{} {{...}}", value.struct_type.name);
+        }
+        if value.fields.is_empty() {
+          return write!(f, "{} {{}}", value.struct_type.name);
+        }
+        self.seen.push(ptr);
+        write!(f, "{} {{", value.struct_type.name)?;
+        for (i, (name, field)) in value.struct_type.fields.iter().zip(value.fields.iter()).enumerate() {
+          if !self.pretty && i == 0 {
+            write!(f, " ")?;
+          }
+          self.separator(f, i, depth + 1)?;
+          write!(f, "{}: ", name)?;
+          self.write(f, field, depth + 1, true)?;
+        }
+        if !self.pretty {
+          write!(f, " ")?;
+        }
+        self.close(f, depth, "}")?;
+        self.seen.pop();
+        Ok(())
+      },
+      RuntimeValue::EnumType(enum_type) => write!(f, "<enum {}>", enum_type.name),
+      RuntimeValue::Variant { enum_type, index } => {
+        let variant = &enum_type.variants[*index];
+        write!(f, "<variant {}::{}({})>", enum_type.name, variant.name, variant.fields.join(", "))
+      },
+      RuntimeValue::Enum(value) => {
+        let ptr = Rc::as_ptr(value) as *const ();
+        let value = value.borrow();
+        write!(f, "{}::{}", value.enum_type.name, value.variant().name)?;
+        if value.values.is_empty() {
+          return Ok(());
+        }
+        if self.seen.contains(&ptr) {
+          return write!(f, "(...)");
+        }
+        self.seen.push(ptr);
+        write!(f, "(")?;
+        for (i, element) in value.values.iter().enumerate() {
+          self.separator(f, i, depth + 1)?;
+          self.write(f, element, depth + 1, true)?;
+        }
+        self.close(f, depth, ")")?;
+        self.seen.pop();
+        Ok(())
+      },
+      RuntimeValue::Object(object) => {
+        let ptr = Rc::as_ptr(object) as *const ();
+        if self.seen.contains(&ptr) {
+          return write!(f, "{{...}}");
+        }
+        let object = object.borrow();
+        if object.properties.is_empty() {
+          return write!(f, "{{}}");
+        }
+        self.seen.push(ptr);
+        let mut keys: Vec<&String> = object.properties.keys().collect();
+        keys.sort();
+        write!(f, "{{")?;
+        for (i, key) in keys.into_iter().enumerate() {
+          if !self.pretty && i == 0 {
+            write!(f, " ")?;
+          }
+          self.separator(f, i, depth + 1)?;
+          write_key(f, key)?;
+          write!(f, ": ")?;
+          self.write(f, &object.properties[key], depth + 1, true)?;
+        }
+        if !self.pretty {
+          write!(f, " ")?;
+        }
+        self.close(f, depth, "}")?;
+        self.seen.pop();
+        Ok(())
+      }
+    }
+  }
+
+  fn separator(&self, f: &mut fmt::Formatter<'_>, index: usize, depth: usize) -> fmt::Result {
+    if index > 0 {
+      write!(f, ",")?;
+    }
+    if self.pretty {
+      write!(f, "\n{}", "  ".repeat(depth))
+    } else if index > 0 {
+      write!(f, " ")
+    } else {
+      Ok(())
+    }
+  }
+
+  fn close(&self, f: &mut fmt::Formatter<'_>, depth: usize, bracket: &str) -> fmt::Result {
+    if self.pretty {
+      write!(f, "\n{}", "  ".repeat(depth))?;
+    }
+    write!(f, "{}", bracket)
+  }
+}
+
+fn write_number(f: &mut fmt::Formatter<'_>, value: f32) -> fmt::Result {
+  if value.is_nan() {
+    write!(f, "NaN")
+  } else if value.is_infinite() {
+    write!(f, "{}", if value > 0.0 { "inf" } else { "-inf" })
+  } else if value == 0.0 {
+    // no "-0"
+    write!(f, "0")
+  } else {
+    write!(f, "{}", value)
+  }
+}
+
+fn write_key(f: &mut fmt::Formatter<'_>, key: &str) -> fmt::Result {
+  let mut chars = key.chars();
+  let is_ident = matches!(chars.next(), Some('a'..='z' | 'A'..='Z' | '_'))
+    && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+  if is_ident {
+    write!(f, "{}", key)
+  } else {
+    write!(f, "\"{}\"", key.escape_debug())
+  }
+}
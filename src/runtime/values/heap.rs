@@ -0,0 +1,255 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use crate::runtime::values::{EnumValue, Object, RuntimeValue, StructType, StructValue};
+
+// Every array, object, struct value, enum value, iterator and scope is registered on the heap when it is created.
+// They are still reference counted, the heap is there to find the reference cycles
+// that counting alone never frees, like an object holding a function whose scope
+// holds that object.
+//
+// Collection works by trial deletion. A strong reference that doesn't come from
+// another node on the heap is held by the environment chain or by the evaluation
+// stack of the interpreter, so the nodes that have such references are the roots.
+// Nodes that can't be reached from a root are kept alive only by cycles and get
+// cleared, which breaks the cycle and lets the counts free them.
+
+pub trait Trace {
+  // Calls `visit` with every heap node this node refers to directly. Returns false
+  // when the node is borrowed and can't be inspected, it is then treated as a root.
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) -> bool;
+  fn clear(&self);
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GcConfig {
+  pub enabled: bool,
+  // allocations before the first automatic collection, and the least between two
+  pub threshold: usize,
+  // after a collection the next one happens once `live * growth` more nodes were allocated
+  pub growth: f32
+}
+
+impl Default for GcConfig {
+  fn default() -> GcConfig {
+    GcConfig { enabled: true, threshold: 10_000, growth: 2.0 }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GcStats {
+  pub collections: usize,
+  pub allocations: usize,
+  pub live: usize,
+  pub freed: usize,
+  pub last_freed: usize,
+  pub next_collection: usize
+}
+
+struct Heap {
+  nodes: Vec<Weak<dyn Trace>>,
+  config: GcConfig,
+  stats: GcStats,
+  since_collection: usize
+}
+
+thread_local! {
+  static HEAP: RefCell<Heap> = RefCell::new(Heap {
+    nodes: Vec::new(),
+    config: GcConfig::default(),
+    stats: GcStats { next_collection: GcConfig::default().threshold, ..GcStats::default() },
+    since_collection: 0
+  });
+}
+
+pub fn register(node: Weak<dyn Trace>) {
+  let collect_now = HEAP.with(|heap| {
+    let mut heap = heap.borrow_mut();
+    heap.nodes.push(node);
+    heap.stats.allocations += 1;
+    heap.since_collection += 1;
+    heap.config.enabled && heap.since_collection >= heap.stats.next_collection
+  });
+  if collect_now {
+    collect();
+  }
+}
+
+pub fn collect() -> GcStats {
+  let nodes: Vec<Rc<dyn Trace>> = HEAP.with(|heap| {
+    heap.borrow().nodes.iter().filter_map(Weak::upgrade).collect()
+  });
+  let index: HashMap<*const (), usize> = nodes.iter()
+    .enumerate()
+    .map(|(i, node)| (Rc::as_ptr(node) as *const (), i))
+    .collect();
+
+  // start from every strong reference, minus the one `nodes` holds, and take away
+  // the references coming from other nodes
+  let mut external: Vec<usize> = nodes.iter().map(|node| Rc::strong_count(node) - 1).collect();
+  let mut stack = Vec::new();
+  for (i, node) in nodes.iter().enumerate() {
+    let traced = node.trace(&mut |child| {
+      if let Some(&j) = index.get(&child) {
+        external[j] = external[j].saturating_sub(1);
+      }
+    });
+    if !traced {
+      stack.push(i);
+    }
+  }
+  stack.extend((0..nodes.len()).filter(|&i| external[i] > 0));
+
+  let mut reachable = vec![false; nodes.len()];
+  while let Some(i) = stack.pop() {
+    if reachable[i] {
+      continue;
+    }
+    reachable[i] = true;
+    nodes[i].trace(&mut |child| {
+      if let Some(&j) = index.get(&child) {
+        stack.push(j);
+      }
+    });
+  }
+
+  let mut freed = 0;
+  for (i, node) in nodes.iter().enumerate() {
+    if !reachable[i] {
+      node.clear();
+      freed += 1;
+    }
+  }
+  drop(nodes);
+
+  HEAP.with(|heap| {
+    let mut heap = heap.borrow_mut();
+    heap.nodes.retain(|node| node.strong_count() > 0);
+    let live = heap.nodes.len();
+    let next = (live as f32 * heap.config.growth) as usize;
+    heap.since_collection = 0;
+    heap.stats.collections += 1;
+    heap.stats.live = live;
+    heap.stats.freed += freed;
+    heap.stats.last_freed = freed;
+    heap.stats.next_collection = next.max(heap.config.threshold);
+    heap.stats
+  })
+}
+
+pub fn stats() -> GcStats {
+  HEAP.with(|heap| {
+    let mut heap = heap.borrow_mut();
+    heap.stats.live = heap.nodes.iter().filter(|node| node.strong_count() > 0).count();
+    heap.stats
+  })
+}
+
+pub fn config() -> GcConfig {
+  HEAP.with(|heap| heap.borrow().config)
+}
+
+pub fn configure(config: GcConfig) {
+  HEAP.with(|heap| {
+    let mut heap = heap.borrow_mut();
+    heap.config = config;
+    heap.stats.next_collection = config.threshold.max((heap.stats.live as f32 * config.growth) as usize);
+  });
+}
+
+impl RuntimeValue {
+  pub fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+    match self {
+      RuntimeValue::Array { elements } => visit(Rc::as_ptr(elements) as *const ()),
+      RuntimeValue::Object(object) => visit(Rc::as_ptr(object) as *const ()),
+      RuntimeValue::Function { decl_env, .. } => visit(decl_env.heap_ptr()),
+      RuntimeValue::StructType(struct_type) => visit(Rc::as_ptr(struct_type) as *const ()),
+      RuntimeValue::Struct(value) => visit(Rc::as_ptr(value) as *const ()),
+      RuntimeValue::Enum(value) => visit(Rc::as_ptr(value) as *const ()),
+      RuntimeValue::Iterator(iterator) => visit(Rc::as_ptr(iterator) as *const ()),
+      _ => ()
+    }
+  }
+}
+
+impl Trace for RefCell<Vec<RuntimeValue>> {
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) -> bool {
+    let Ok(elements) = self.try_borrow() else {
+      return false;
+    };
+    for element in elements.iter() {
+      element.trace(visit);
+    }
+    true
+  }
+
+  fn clear(&self) {
+    self.borrow_mut().clear();
+  }
+}
+
+impl Trace for RefCell<Object> {
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) -> bool {
+    let Ok(object) = self.try_borrow() else {
+      return false;
+    };
+    for value in object.properties.values() {
+      value.trace(visit);
+    }
+    true
+  }
+
+  fn clear(&self) {
+    self.borrow_mut().properties.clear();
+  }
+}
+
+impl Trace for StructType {
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) -> bool {
+    let Ok(methods) = self.methods.try_borrow() else {
+      return false;
+    };
+    for method in methods.values() {
+      method.trace(visit);
+    }
+    true
+  }
+
+  fn clear(&self) {
+    self.methods.borrow_mut().clear();
+  }
+}
+
+impl Trace for RefCell<StructValue> {
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) -> bool {
+    let Ok(value) = self.try_borrow() else {
+      return false;
+    };
+    visit(Rc::as_ptr(&value.struct_type) as *const ());
+    for field in value.fields.iter() {
+      field.trace(visit);
+    }
+    true
+  }
+
+  fn clear(&self) {
+    self.borrow_mut().fields.clear();
+  }
+}
+
+impl Trace for RefCell<EnumValue> {
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) -> bool {
+    let Ok(value) = self.try_borrow() else {
+      return false;
+    };
+    for value in value.values.iter() {
+      value.trace(visit);
+    }
+    true
+  }
+
+  fn clear(&self) {
+    self.borrow_mut().values.clear();
+  }
+}
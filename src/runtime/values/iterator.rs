@@ -0,0 +1,67 @@
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::errors::{throw, ErrorKind, Eval};
+use crate::runtime::values::{heap, RuntimeValue};
+use crate::runtime::values::heap::Trace;
+
+// A source of values produced one at a time. Natives implement this to hand out
+// lazy sequences, `runtime::iteration` has the ones for the built-in types.
+pub trait NativeIterator {
+  // The next value, or `None` once the iterator is done.
+  fn next(&mut self, interpreter: &mut Interpreter) -> Eval<Option<RuntimeValue>>;
+  // Like `Trace::trace`, calls `visit` with the heap nodes the iterator holds on to.
+  fn trace(&self, visit: &mut dyn FnMut(*const ()));
+}
+
+// The value an iterator is stored in. It is shared: every alias advances the
+// same iterator.
+pub struct IteratorValue {
+  source: RefCell<Box<dyn NativeIterator>>
+}
+
+impl IteratorValue {
+  pub fn new(source: impl NativeIterator + 'static) -> Rc<IteratorValue> {
+    let iterator = Rc::new(IteratorValue { source: RefCell::new(Box::new(source)) });
+    heap::register(Rc::downgrade(&iterator) as Weak<dyn Trace>);
+    iterator
+  }
+
+  pub fn next(&self, interpreter: &mut Interpreter) -> Eval<Option<RuntimeValue>> {
+    let Ok(mut source) = self.source.try_borrow_mut() else {
+      throw!(ErrorKind::Value, "An iterator can't be advanced while it is producing its next value");
+    };
+    source.next(interpreter)
+  }
+}
+
+impl PartialEq for IteratorValue {
+  fn eq(&self, other: &IteratorValue) -> bool {
+    std::ptr::eq(self, other)
+  }
+}
+
+struct Exhausted;
+
+impl NativeIterator for Exhausted {
+  fn next(&mut self, _: &mut Interpreter) -> Eval<Option<RuntimeValue>> {
+    Ok(None)
+  }
+
+  fn trace(&self, _: &mut dyn FnMut(*const ())) {}
+}
+
+impl Trace for IteratorValue {
+  fn trace(&self, visit: &mut dyn FnMut(*const ())) -> bool {
+    let Ok(source) = self.source.try_borrow() else {
+      return false;
+    };
+    source.trace(visit);
+    true
+  }
+
+  fn clear(&self) {
+    *self.source.borrow_mut() = Box::new(Exhausted);
+  }
+}
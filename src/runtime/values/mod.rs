@@ -0,0 +1,257 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use crate::runtime::environment::Environment;
+use crate::runtime::errors::Eval;
+use crate::runtime::interpreter::Interpreter;
+use crate::frontend::ast::{Param, Stmt, Variant};
+use crate::runtime::values::heap::Trace;
+use crate::runtime::values::iterator::{IteratorValue, NativeIterator};
+use crate::runtime::values::range::Range;
+
+pub mod compare;
+pub mod display;
+pub mod heap;
+pub mod iterator;
+pub mod range;
+
+// The body of a native, and of one that keeps no state, which the built-in ones are.
+pub type NativeFn = dyn Fn(Vec<RuntimeValue>, &mut Interpreter) -> Eval;
+pub type NativeBody = fn(Vec<RuntimeValue>, &mut Interpreter) -> Eval;
+
+// Arrays, objects and struct values live behind shared references: copying the value (assigning it,
+// passing it to a function, storing it in another structure) aliases the same storage.
+#[derive(Clone, PartialEq)]
+pub enum RuntimeValue {
+  Null,
+  Number {
+    value: f32
+  },
+  Bool {
+    value: bool
+  },
+  Object(Rc<RefCell<Object>>),
+  NativeFunction(Rc<NativeFunction>),
+  Function {
+    name: String,
+    params: Vec<Param>,
+    decl_env: Environment,
+    body: Vec<Stmt>
+  },
+  String {
+    value: String
+  },
+  Array {
+    elements: Rc<RefCell<Vec<RuntimeValue>>>
+  },
+  StructType(Rc<StructType>),
+  Struct(Rc<RefCell<StructValue>>),
+  EnumType(Rc<EnumType>),
+  // `Shape::Circle` before it is called with the payload
+  Variant {
+    enum_type: Rc<EnumType>,
+    index: usize
+  },
+  Enum(Rc<RefCell<EnumValue>>),
+  Range(Range),
+  Iterator(Rc<IteratorValue>),
+  // what a user iterator's `next` returns when there are no more values
+  Done
+}
+
+// A function written in Rust. It gets the arguments and a handle to the interpreter,
+// which it can call the functions it is given with. The body is a closure, so it can
+// keep state of its own, like a counter or a connection a host shares with scripts.
+// Values it holds are not seen by the garbage collector and stay alive with it.
+pub struct NativeFunction {
+  pub name: String,
+  pub arity: Arity,
+  pub body: Box<NativeFn>
+}
+
+// Natives are only equal to themselves.
+impl PartialEq for NativeFunction {
+  fn eq(&self, other: &NativeFunction) -> bool {
+    std::ptr::eq(self, other)
+  }
+}
+
+// The number of arguments a native takes, checked before it is called.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+  Exact(usize),
+  Between(usize, usize),
+  AtLeast(usize)
+}
+
+impl Arity {
+  pub fn allows(&self, count: usize) -> bool {
+    match *self {
+      Arity::Exact(arity) => count == arity,
+      Arity::Between(min, max) => (min..=max).contains(&count),
+      Arity::AtLeast(min) => count >= min
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object {
+  pub properties: HashMap<String, RuntimeValue>,
+  // set by `Object::freeze`, a frozen object's properties can't change
+  pub frozen: bool
+}
+
+// Declared with `struct Name { fields }`, `impl Name { }` blocks add to its methods.
+pub struct StructType {
+  pub name: String,
+  pub fields: Vec<String>,
+  pub methods: RefCell<HashMap<String, RuntimeValue>>
+}
+
+// Types are only equal to themselves.
+impl PartialEq for StructType {
+  fn eq(&self, other: &StructType) -> bool {
+    std::ptr::eq(self, other)
+  }
+}
+
+// Field values are stored in the order the struct declares its fields.
+#[derive(Clone, PartialEq)]
+pub struct StructValue {
+  pub struct_type: Rc<StructType>,
+  pub fields: Vec<RuntimeValue>
+}
+
+// Declared with `enum Name { Variant(fields), Other }`.
+pub struct EnumType {
+  pub name: String,
+  pub variants: Vec<Variant>
+}
+
+impl PartialEq for EnumType {
+  fn eq(&self, other: &EnumType) -> bool {
+    std::ptr::eq(self, other)
+  }
+}
+
+// The payload is stored in the order the variant declares its fields.
+#[derive(Clone, PartialEq)]
+pub struct EnumValue {
+  pub enum_type: Rc<EnumType>,
+  pub variant: usize,
+  pub values: Vec<RuntimeValue>
+}
+
+impl EnumType {
+  pub fn variant_index(&self, variant: &str) -> Option<usize> {
+    self.variants.iter().position(|candidate| candidate.name == variant)
+  }
+}
+
+impl EnumValue {
+  pub fn variant(&self) -> &Variant {
+    &self.enum_type.variants[self.variant]
+  }
+}
+
+impl StructType {
+  pub fn field_index(&self, field: &str) -> Option<usize> {
+    self.fields.iter().position(|name| name == field)
+  }
+}
+
+impl RuntimeValue {
+  pub fn array(elements: Vec<RuntimeValue>) -> RuntimeValue {
+    let elements = Rc::new(RefCell::new(elements));
+    heap::register(Rc::downgrade(&elements) as Weak<dyn Trace>);
+    RuntimeValue::Array { elements }
+  }
+
+  pub fn object(properties: HashMap<String, RuntimeValue>) -> RuntimeValue {
+    let object = Rc::new(RefCell::new(Object { properties, frozen: false }));
+    heap::register(Rc::downgrade(&object) as Weak<dyn Trace>);
+    RuntimeValue::Object(object)
+  }
+
+  pub fn struct_type(name: String, fields: Vec<String>) -> RuntimeValue {
+    let struct_type = Rc::new(StructType { name, fields, methods: RefCell::new(HashMap::new()) });
+    heap::register(Rc::downgrade(&struct_type) as Weak<dyn Trace>);
+    RuntimeValue::StructType(struct_type)
+  }
+
+  pub fn struct_value(struct_type: Rc<StructType>, fields: Vec<RuntimeValue>) -> RuntimeValue {
+    let value = Rc::new(RefCell::new(StructValue { struct_type, fields }));
+    heap::register(Rc::downgrade(&value) as Weak<dyn Trace>);
+    RuntimeValue::Struct(value)
+  }
+
+  pub fn enum_type(name: String, variants: Vec<Variant>) -> RuntimeValue {
+    RuntimeValue::EnumType(Rc::new(EnumType { name, variants }))
+  }
+
+  pub fn enum_value(enum_type: Rc<EnumType>, variant: usize, values: Vec<RuntimeValue>) -> RuntimeValue {
+    let value = Rc::new(RefCell::new(EnumValue { enum_type, variant, values }));
+    heap::register(Rc::downgrade(&value) as Weak<dyn Trace>);
+    RuntimeValue::Enum(value)
+  }
+
+  pub fn native(name: &str, arity: Arity, body: impl Fn(Vec<RuntimeValue>, &mut Interpreter) -> Eval + 'static) -> RuntimeValue {
+    RuntimeValue::NativeFunction(Rc::new(NativeFunction { name: name.to_string(), arity, body: Box::new(body) }))
+  }
+
+  pub fn iterator(source: impl NativeIterator + 'static) -> RuntimeValue {
+    RuntimeValue::Iterator(IteratorValue::new(source))
+  }
+
+  // The runtime type tag returned by `type_of!`, struct and enum values report the name of their type.
+  pub fn type_name(&self) -> String {
+    match self {
+      RuntimeValue::Null => "null".to_string(),
+      RuntimeValue::Number { .. } => "number".to_string(),
+      RuntimeValue::Bool { .. } => "bool".to_string(),
+      RuntimeValue::String { .. } => "string".to_string(),
+      RuntimeValue::Array { .. } => "array".to_string(),
+      RuntimeValue::Object(_) => "object".to_string(),
+      RuntimeValue::Function { .. } | RuntimeValue::NativeFunction(_) | RuntimeValue::Variant { .. } => "function".to_string(),
+      RuntimeValue::StructType(_) => "struct".to_string(),
+      RuntimeValue::Struct(value) => value.borrow().struct_type.name.clone(),
+      RuntimeValue::EnumType(_) => "enum".to_string(),
+      RuntimeValue::Enum(value) => value.borrow().enum_type.name.clone(),
+      RuntimeValue::Range(_) => "range".to_string(),
+      RuntimeValue::Iterator(_) => "iterator".to_string(),
+      RuntimeValue::Done => "done".to_string()
+    }
+  }
+
+  // What `if` tests: only `false` and `null` are false.
+  pub fn is_truthy(&self) -> bool {
+    !matches!(self, RuntimeValue::Bool { value: false } | RuntimeValue::Null)
+  }
+
+  // Identity: arrays, objects, struct values and enum values are the same only when they
+  // share storage, every other value is compared by value.
+  pub fn is_same(&self, other: &RuntimeValue) -> bool {
+    match (self, other) {
+      (RuntimeValue::Array { elements: lhs }, RuntimeValue::Array { elements: rhs }) => Rc::ptr_eq(lhs, rhs),
+      (RuntimeValue::Object(lhs), RuntimeValue::Object(rhs)) => Rc::ptr_eq(lhs, rhs),
+      (RuntimeValue::Struct(lhs), RuntimeValue::Struct(rhs)) => Rc::ptr_eq(lhs, rhs),
+      (RuntimeValue::Enum(lhs), RuntimeValue::Enum(rhs)) => Rc::ptr_eq(lhs, rhs),
+      (lhs, rhs) => lhs == rhs
+    }
+  }
+
+  // A shallow copy: the new array or object gets its own storage, nested arrays
+  // and objects are still shared.
+  pub fn shallow_copy(&self) -> RuntimeValue {
+    match self {
+      RuntimeValue::Array { elements } => RuntimeValue::array(elements.borrow().clone()),
+      RuntimeValue::Object(object) => RuntimeValue::object(object.borrow().properties.clone()),
+      RuntimeValue::Struct(value) => {
+        let value = value.borrow();
+        RuntimeValue::struct_value(value.struct_type.clone(), value.fields.clone())
+      },
+      value => value.clone()
+    }
+  }
+}
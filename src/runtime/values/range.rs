@@ -0,0 +1,107 @@
+use std::fmt;
+
+use crate::runtime::errors::{throw, ErrorKind, Eval};
+
+// `start..end`, `start..=end` and `(start..end).step(n)`. Either end may be left
+// out, which is only useful for slicing: `arr[2..]`, `s[..5]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+  pub start: Option<f32>,
+  pub end: Option<f32>,
+  pub inclusive: bool,
+  pub step: f32
+}
+
+impl Range {
+  // The numbers the range counts through, computed one at a time so a long range
+  // never needs to be stored. A range without an end counts forever.
+  pub fn iter(&self) -> Eval<impl Iterator<Item = f32>> {
+    let range = *self;
+    let Some(start) = range.start else {
+      throw!(ErrorKind::Value, "Cannot iterate over {}, it has no start", range);
+    };
+    Ok(range.iter_from(start))
+  }
+
+  fn iter_from(&self, start: f32) -> impl Iterator<Item = f32> {
+    let range = *self;
+    (0..).map(move |i| range.nth(start, i)).take_while(move |value| range.before_end(*value))
+  }
+
+  fn nth(&self, start: f32, i: usize) -> f32 {
+    start + i as f32 * self.step
+  }
+
+  fn before_end(&self, value: f32) -> bool {
+    match (self.end, self.inclusive, self.step > 0.0) {
+      (None, _, _) => true,
+      (Some(end), true, true) => value <= end,
+      (Some(end), false, true) => value < end,
+      (Some(end), true, false) => value >= end,
+      (Some(end), false, false) => value > end
+    }
+  }
+
+  pub fn contains(&self, value: f32) -> bool {
+    let Some(start) = self.start else {
+      return self.before_end(value);
+    };
+    let past_start = if self.step > 0.0 { value >= start } else { value <= start };
+    past_start && self.before_end(value) && ((value - start) / self.step).fract() == 0.0
+  }
+
+  pub(crate) fn len(&self) -> Option<usize> {
+    match (self.start, self.end) {
+      // worked out from the span, then moved by the few numbers rounding can put on
+      // the other side of the end, so it is always what iterating would count
+      (Some(start), Some(end)) => {
+        let mut len = ((end - start) / self.step).ceil().max(0.0) as usize;
+        while len > 0 && !self.before_end(self.nth(start, len - 1)) {
+          len -= 1;
+        }
+        while len < usize::MAX && self.before_end(self.nth(start, len)) {
+          len += 1;
+        }
+        Some(len)
+      },
+      _ => None
+    }
+  }
+
+  // The indices this range selects from something `len` long, for slicing. The
+  // ends are clamped to the length, a step walks over the indices in between.
+  pub fn slice_indices(&self, len: usize) -> Eval<Vec<usize>> {
+    if self.step <= 0.0 || self.step.fract() != 0.0 {
+      throw!(ErrorKind::Value, "Cannot slice with {}, the step has to be a positive whole number", self);
+    }
+    let whole = |value: f32| value >= 0.0 && value.fract() == 0.0;
+    if !self.start.is_none_or(whole) || !self.end.is_none_or(whole) {
+      throw!(ErrorKind::Value, "Cannot slice with {}, the bounds have to be whole numbers of at least 0", self);
+    }
+    let bound = |value: f32| (value as usize).min(len);
+    let start = self.start.map_or(0, bound);
+    let end = match self.end {
+      Some(end) if self.inclusive => (bound(end) + 1).min(len),
+      Some(end) => bound(end),
+      None => len
+    };
+    Ok((start..end.max(start)).step_by(self.step as usize).collect())
+  }
+}
+
+impl fmt::Display for Range {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let number = |value: f32| crate::runtime::values::RuntimeValue::Number { value }.to_string();
+    let range = format!(
+      "{}{}{}",
+      self.start.map(number).unwrap_or_default(),
+      if self.inclusive { "..=" } else { ".." },
+      self.end.map(number).unwrap_or_default()
+    );
+    if self.step == 1.0 {
+      write!(f, "{}", range)
+    } else {
+      write!(f, "({}).step({})", range, number(self.step))
+    }
+  }
+}
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+
+use vigscript::runtime::format::format;
+use vigscript::runtime::values::RuntimeValue;
+
+fn number(value: f32) -> RuntimeValue {
+  RuntimeValue::Number { value }
+}
+
+#[test]
+fn positional_placeholders() {
+  assert_eq!(format("{1} {0} {}", &[number(1.0), number(2.0)], &HashMap::new()), Ok("2 1 1".to_string()));
+}
+
+#[test]
+fn missing_arguments_are_an_error() {
+  assert_eq!(
+    format("{3}", &[number(1.0)], &HashMap::new()),
+    Err("Format string refers to argument 3 but 1 positional argument was given".to_string())
+  );
+}
+
+#[test]
+fn indices_too_large_for_a_usize_are_missing_arguments() {
+  assert_eq!(
+    format("{99999999999999999999999}", &[number(1.0)], &HashMap::new()),
+    Err("Format string refers to argument 99999999999999999999999 but 1 positional argument was given".to_string())
+  );
+}
@@ -0,0 +1,124 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use vigscript::frontend::{lexer, parser::Parser};
+use vigscript::runtime::environment::{register_native, Environment};
+use vigscript::runtime::errors::{describe_uncaught, Interrupt};
+use vigscript::runtime::interpreter::evaluate;
+use vigscript::runtime::modules::{set_loader, set_main, MemoryLoader};
+use vigscript::runtime::prelude::global_environment;
+use vigscript::runtime::values::{Arity, RuntimeValue};
+
+// Runs `main.vig` with the other modules in a `MemoryLoader`. Tests run on threads of
+// their own, so each one starts with an empty module cache.
+fn run(main: &str, modules: &[(&str, &str)]) -> Result<Environment, String> {
+  let mut loader = MemoryLoader::new();
+  loader.insert("main.vig", main);
+  for (name, source) in modules {
+    loader.insert(name, source);
+  }
+  set_loader(Rc::new(loader));
+  set_main("main.vig");
+  let mut env = global_environment();
+  let tokens = lexer::tokenize(main);
+  let program = Parser::new(&tokens).produce_ast();
+  match evaluate(program, &mut env) {
+    Err(Interrupt::Throw(value)) => Err(describe_uncaught(&value)),
+    _ => Ok(env)
+  }
+}
+
+fn number(env: &mut Environment, name: &str) -> f32 {
+  match env.lookup_var(name.to_string()) {
+    Ok(RuntimeValue::Number { value }) => value,
+    _ => panic!("{} isn't a number", name)
+  }
+}
+
+#[test]
+fn named_imports() {
+  let mut env = run(
+    "import { inc, start } from \"./lib/math.vig\";\nlet res = inc(start);",
+    &[("lib/math.vig", "export const start = 41;\nexport func inc(x) {\n  x + 1\n}")]
+  ).unwrap();
+  assert_eq!(number(&mut env, "res"), 42.0);
+}
+
+#[test]
+fn namespace_imports() {
+  let mut env = run(
+    "import \"./lib/math.vig\" as math;\nlet res = math::inc(math::start);",
+    &[("lib/math.vig", "export const start = 1;\nexport func inc(x) {\n  x + 1\n}")]
+  ).unwrap();
+  assert_eq!(number(&mut env, "res"), 2.0);
+}
+
+#[test]
+fn relative_imports_resolve_from_the_importer() {
+  let mut env = run(
+    "import { value } from \"./lib/a.vig\";",
+    &[("lib/a.vig", "import { inner } from \"../shared/b.vig\";\nexport let value = inner * 2;"), ("shared/b.vig", "export let inner = 21;")]
+  ).unwrap();
+  assert_eq!(number(&mut env, "value"), 42.0);
+}
+
+#[test]
+fn a_module_runs_once() {
+  let runs = Rc::new(Cell::new(0));
+  let counter = runs.clone();
+  register_native("ran!", Arity::Exact(0), move |_, _| {
+    counter.set(counter.get() + 1);
+    Ok(RuntimeValue::Null)
+  });
+  let mut env = run(
+    "import \"./counted.vig\" as first;\nimport \"./other.vig\" as other;\nlet shared = same!(first::items, other::items);",
+    &[
+      ("counted.vig", "ran!()\nexport let items = [];"),
+      ("other.vig", "import \"./counted.vig\" as counted;\nexport let items = counted::items;")
+    ]
+  ).unwrap();
+  assert_eq!(runs.get(), 1);
+  assert_eq!(env.lookup_var("shared".to_string()).ok(), Some(RuntimeValue::Bool { value: true }));
+}
+
+#[test]
+fn module_namespaces_are_frozen() {
+  let err = run(
+    "import \"./util.vig\" as util;\nutil::PI = 4",
+    &[("util.vig", "export const PI = 3;")]
+  ).unwrap_err();
+  assert!(err.contains("TypeError"), "{}", err);
+  assert!(err.contains("Can't assign to PI of a frozen object"), "{}", err);
+
+  let mut env = run(
+    "import \"./first.vig\" as first;\nimport \"./util.vig\" as util;\nlet pi = util::PI;",
+    &[("util.vig", "export const PI = 3;"), ("first.vig", "import \"./util.vig\" as util;\ntry {\n  util::PI = 4\n} catch {}")]
+  ).unwrap();
+  assert_eq!(number(&mut env, "pi"), 3.0);
+}
+
+#[test]
+fn import_cycles_show_the_chain() {
+  let err = run(
+    "import \"./a.vig\" as a;",
+    &[("a.vig", "import \"./b.vig\" as b;"), ("b.vig", "import \"./main.vig\" as main;")]
+  ).unwrap_err();
+  assert!(err.contains("ImportError"), "{}", err);
+  assert!(err.contains("Import cycle: main.vig -> a.vig -> b.vig -> main.vig"), "{}", err);
+}
+
+#[test]
+fn missing_exports_are_an_import_error() {
+  let err = run(
+    "import { inc, dec } from \"./math.vig\";",
+    &[("math.vig", "export func inc(x) {\n  x + 1\n}\nfunc dec(x) {\n  x - 1\n}")]
+  ).unwrap_err();
+  assert!(err.contains("ImportError"), "{}", err);
+  assert!(err.contains("./math.vig has no export named dec"), "{}", err);
+}
+
+#[test]
+fn missing_modules_are_an_import_error() {
+  let err = run("import \"./nowhere.vig\" as nowhere;", &[]).unwrap_err();
+  assert!(err.contains("Cannot find module \"./nowhere.vig\""), "{}", err);
+}